@@ -0,0 +1,51 @@
+//! Evaluating large pasted inputs, and editing them one keystroke at a time.
+//!
+//! Run with `cargo bench --bench evaluate`.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use unix_time_calculator::{Settings, Worksheet};
+
+/// Lines of a worksheet, like a pasted log with a running delta between entries.
+const LINES: usize = 20_000;
+
+fn input() -> String {
+    (0..LINES)
+        .map(|i| match i % 4 {
+            0 => format!("{}", 1_748_000_000 + i * 17),
+            1 => format!(r#"{{"ts": {}}}"#, 1_748_000_000 + i * 17),
+            2 => "#prev - #-2 in s".to_string(),
+            _ => "now - 1h".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn evaluate(c: &mut Criterion) {
+    let input = input();
+    let mut group = c.benchmark_group("evaluate");
+    group.sample_size(10);
+    group.bench_function("paste", |b| {
+        b.iter(|| Worksheet::new(input.clone(), 0));
+    });
+
+    // Typing on the last line, and the clock ticking, only evaluate what changed.
+    let mut sheet = Worksheet::new(input.clone(), 0);
+    let mut edited = input.clone();
+    group.bench_function("keystroke", |b| {
+        b.iter(|| {
+            edited.push('1');
+            sheet.update(edited.clone(), 0, Settings::default());
+        });
+    });
+    let mut now = 0;
+    group.bench_function("tick", |b| {
+        b.iter(|| {
+            now += 1;
+            sheet.update(input.clone(), now, Settings::default());
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, evaluate);
+criterion_main!(benches);
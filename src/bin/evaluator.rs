@@ -0,0 +1,8 @@
+//! The web worker that evaluates large worksheets, see `unix_time_calculator::worker`.
+
+use gloo_worker::Registrable;
+use unix_time_calculator::worker::Evaluator;
+
+fn main() {
+    Evaluator::registrar().register();
+}
@@ -0,0 +1,257 @@
+//! `utc annotate`: a streaming filter that adds human datetimes next to epoch numbers.
+//!
+//! Input is read one line at a time and written out as soon as it is annotated, so the
+//! filter works behind `tail -f` and never holds more than a single line in memory, or a
+//! chunk of one that is very long.
+
+use std::io::{self, BufRead, Read, Write};
+
+use clap::{Args, ValueEnum};
+use unix_time_calculator::parser::find_epochs;
+use unix_time_calculator::{Record, Settings};
+
+/// Lines longer than this are annotated in chunks of about this many bytes.
+const MAX_LINE: u64 = 64 * 1024;
+
+/// The longest unfinished word carried over from one chunk of a line to the next.
+const MAX_CARRY: usize = MAX_LINE as usize / 2;
+
+#[derive(Args)]
+pub struct Annotate {
+    /// Where to put the datetimes.
+    #[arg(short, long, value_enum, default_value_t)]
+    mode: Mode,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum Mode {
+    /// After the end of the line, as `# datetime, datetime`.
+    #[default]
+    Append,
+    /// Right after every epoch, as `epoch (datetime)`.
+    Inline,
+}
+
+impl Annotate {
+    pub fn run(&self, settings: &Settings) -> io::Result<()> {
+        let stdin = io::stdin().lock();
+        let stdout = io::stdout().lock();
+        self.filter(stdin, stdout, settings)
+    }
+
+    fn filter(
+        &self,
+        mut input: impl BufRead,
+        mut output: impl Write,
+        settings: &Settings,
+    ) -> io::Result<()> {
+        // The unfinished end of the last chunk, if it did not end the line.
+        let mut buffer = vec![];
+        let mut line = Line::default();
+        loop {
+            let read = Read::take(&mut input, MAX_LINE).read_until(b'\n', &mut buffer)?;
+            if buffer.is_empty() {
+                // The last line ended with its last chunk, but without a newline.
+                if let Mode::Append = self.mode
+                    && !line.datetimes.is_empty()
+                {
+                    write!(output, "  # {}", line.datetimes.join(", "))?;
+                }
+                return output.flush();
+            }
+            // Less than a full chunk means the line, or the input, ended.
+            let end = buffer.ends_with(b"\n") || (read as u64) < MAX_LINE;
+            // A chunk that is all unfinished, such as a cut character, is written as it is.
+            let split = match end {
+                true => buffer.len(),
+                false => match unfinished(&buffer) {
+                    0 => buffer.len(),
+                    split => split,
+                },
+            };
+            match std::str::from_utf8(&buffer[..split]) {
+                Ok(chunk) => {
+                    output.write_all(self.annotate(chunk, end, &mut line, settings).as_bytes())?
+                }
+                // Leave lines that are not text untouched.
+                Err(_) => output.write_all(&buffer[..split])?,
+            }
+            output.flush()?;
+            line = match end {
+                true => Line::default(),
+                false => Line {
+                    in_word: is_word(buffer[split - 1]),
+                    ..line
+                },
+            };
+            buffer.drain(..split);
+        }
+    }
+
+    /// Annotates a chunk of `line`, which is all of it or its `end`.
+    fn annotate(&self, chunk: &str, end: bool, line: &mut Line, settings: &Settings) -> String {
+        let text = match end {
+            true => chunk
+                .strip_suffix('\n')
+                .map_or(chunk, |text| text.strip_suffix('\r').unwrap_or(text)),
+            false => chunk,
+        };
+        let newline = &chunk[text.len()..];
+        let epochs = match line.in_word {
+            // Digits right after the word are part of it, not an epoch.
+            true => find_epochs(&format!("_{text}"))
+                .into_iter()
+                .map(|(range, t)| (range.start - 1..range.end - 1, t))
+                .collect(),
+            false => find_epochs(text),
+        };
+        let datetime = |timestamp| {
+            Record::timestamp(timestamp, settings.zone.offset_at(timestamp))
+                .with_format(settings.format.clone())
+                .to_datetime_string()
+        };
+        match self.mode {
+            Mode::Append => {
+                line.datetimes
+                    .extend(epochs.into_iter().map(|(_, t)| datetime(t)));
+                if !end || line.datetimes.is_empty() {
+                    return chunk.to_string();
+                }
+                format!("{text}  # {}{newline}", line.datetimes.join(", "))
+            }
+            Mode::Inline if epochs.is_empty() => chunk.to_string(),
+            Mode::Inline => {
+                let mut annotated = String::with_capacity(text.len() * 2);
+                let mut last = 0;
+                for (range, timestamp) in epochs {
+                    annotated.push_str(&text[last..range.end]);
+                    annotated.push_str(&format!(" ({})", datetime(timestamp)));
+                    last = range.end;
+                }
+                annotated.push_str(&text[last..]);
+                annotated.push_str(newline);
+                annotated
+            }
+        }
+    }
+}
+
+/// What is known of the line being annotated, which is read in chunks when it is long.
+#[derive(Default)]
+struct Line {
+    /// Whether the last chunk ended in the middle of a word, which the next one continues.
+    in_word: bool,
+    /// The datetimes of the epochs found so far, appended at the end of the line.
+    datetimes: Vec<String>,
+}
+
+const fn is_word(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+/// Where the unfinished end of a chunk starts, which is carried over to the next chunk: the
+/// last word or number, so an epoch is never cut in two, or at least a partial UTF-8
+/// character. A word too long to carry is cut instead, see [`Line::in_word`].
+fn unfinished(chunk: &[u8]) -> usize {
+    let word = |byte: &u8| is_word(*byte) || *byte == b'.' || !byte.is_ascii();
+    match chunk.iter().rposition(|byte| !word(byte)) {
+        Some(i) if chunk.len() - (i + 1) <= MAX_CARRY => i + 1,
+        _ => match std::str::from_utf8(chunk) {
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            _ => chunk.len(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::FixedOffset;
+
+    use super::*;
+
+    fn filter(mode: Mode, input: &[u8]) -> Vec<u8> {
+        let mut output = vec![];
+        let settings = Settings {
+            zone: FixedOffset::east_opt(3600).unwrap().into(),
+            ..Settings::default()
+        };
+        Annotate { mode }
+            .filter(input, &mut output, &settings)
+            .unwrap();
+        output
+    }
+
+    #[test]
+    fn append() {
+        assert_eq!(
+            filter(
+                Mode::Append,
+                b"a 1748000000 b 1748000001000\r\nplain\nlast 0"
+            ),
+            concat!(
+                "a 1748000000 b 1748000001000  ",
+                "# 2025-05-23 12:33:20 +01:00, 2025-05-23 12:33:21 +01:00\r\n",
+                "plain\n",
+                "last 0",
+            )
+            .as_bytes()
+        );
+    }
+
+    #[test]
+    fn inline() {
+        assert_eq!(
+            filter(Mode::Inline, b"{\"ts\":1748000000,\"id\":7}\n"),
+            b"{\"ts\":1748000000 (2025-05-23 12:33:20 +01:00),\"id\":7}\n"
+        );
+    }
+
+    #[test]
+    fn long_lines() {
+        // An epoch across the end of the first chunk is found whole, and annotated at the
+        // end of the line rather than of the chunk.
+        let padding = "a ".repeat(MAX_LINE as usize / 2 - 1);
+        let line = format!("{padding}1748000000 b\nnext\n");
+        assert_eq!(
+            String::from_utf8(filter(Mode::Append, line.as_bytes())).unwrap(),
+            format!("{padding}1748000000 b  # 2025-05-23 12:33:20 +01:00\nnext\n")
+        );
+        // A character across it stays whole, as does a word too long to carry over.
+        let padding = "a".repeat(MAX_LINE as usize - 1);
+        let line = format!("{padding}é 1748000000\n");
+        assert_eq!(
+            String::from_utf8(filter(Mode::Inline, line.as_bytes())).unwrap(),
+            format!("{padding}é 1748000000 (2025-05-23 12:33:20 +01:00)\n")
+        );
+        let line = format!("{}1748000000 b\n", "a".repeat(MAX_LINE as usize));
+        assert_eq!(filter(Mode::Append, line.as_bytes()), line.as_bytes());
+    }
+
+    #[test]
+    fn unterminated_lines() {
+        assert_eq!(
+            filter(Mode::Append, b"x 1748000000\n\xc3"),
+            b"x 1748000000  # 2025-05-23 12:33:20 +01:00\n\xc3"
+        );
+        assert_eq!(
+            filter(Mode::Append, b"a 1748000000"),
+            b"a 1748000000  # 2025-05-23 12:33:20 +01:00"
+        );
+        // A last line longer than a chunk, ending right at the end of one, and past it.
+        for length in [MAX_LINE as usize, MAX_LINE as usize + 5] {
+            let line = format!("1748000000{}", " ".repeat(length - 10));
+            assert_eq!(
+                String::from_utf8(filter(Mode::Append, line.as_bytes())).unwrap(),
+                format!("{line}  # 2025-05-23 12:33:20 +01:00")
+            );
+        }
+    }
+
+    #[test]
+    fn binary_passthrough() {
+        assert_eq!(
+            filter(Mode::Inline, b"\xff 1748000000\n"),
+            b"\xff 1748000000\n"
+        );
+    }
+}
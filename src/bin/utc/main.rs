@@ -0,0 +1,222 @@
+//! `utc`: the Unix time calculator on the command line.
+//!
+//! Every argument (or every line read from files or standard input) is one line of the
+//! worksheet, so headers like `#UTC+1` and references like `#2 - #1` work exactly as they
+//! do in the web page. `utc annotate` instead filters free text such as logs.
+
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use unix_time_calculator::parser::{self, Expression};
+use unix_time_calculator::{DateFormat, Export, Record, Settings, Worksheet, Zone};
+
+use crate::annotate::Annotate;
+use crate::repl::Repl;
+use crate::tui::Tui;
+
+mod annotate;
+mod repl;
+mod tui;
+
+#[derive(Parser)]
+#[command(
+    name = "utc",
+    version,
+    about = "Unix time calculator",
+    allow_negative_numbers = true,
+    subcommand_precedence_over_arg = true
+)]
+struct Cli {
+    /// Lines to evaluate. Standard input is read when no lines or files are given.
+    lines: Vec<String>,
+
+    /// Read lines from a file, `-` for standard input. May be repeated.
+    #[arg(short, long = "file", value_name = "FILE")]
+    files: Vec<String>,
+
+    /// Zone used until the first `#UTC` header, e.g. `UTC+5`, `-03:30` or `Europe/Paris`.
+    #[arg(
+        short,
+        long,
+        value_name = "ZONE",
+        default_value = "UTC",
+        value_parser = parse_zone,
+        global = true
+    )]
+    zone: Zone,
+
+    /// Datetime format used until the first `#FORMAT` header: a preset such as `iso`,
+    /// `rfc2822`, `rfc3339ms`, `http`, `date`, `time` or `weekday`, or a strftime pattern.
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "default",
+        value_parser = parse_format,
+        global = true
+    )]
+    format: DateFormat,
+
+    /// What to print for every line.
+    #[arg(short, long, value_enum, default_value_t, global = true)]
+    output: Output,
+
+    /// Value of `now`, as a timestamp or a datetime. Defaults to the system clock.
+    #[arg(long, value_name = "TIME", global = true)]
+    now: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Copy standard input to standard output, adding the datetime of every epoch number.
+    Annotate(Annotate),
+    /// Edit a worksheet in a full-screen terminal interface.
+    Tui(Tui),
+    /// Evaluate lines interactively, numbering every result.
+    Repl(Repl),
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum Output {
+    /// Input lines aligned with their datetimes, as copied from the web page.
+    #[default]
+    Table,
+    /// Only the datetime column.
+    Datetime,
+    /// Only the timestamp column.
+    Timestamp,
+    /// Like `table`, with how long ago or from now every datetime is.
+    Relative,
+    /// Comma-separated values with the input, datetime, timestamp, zone and error columns.
+    Csv,
+    /// Tab-separated values with the same columns as `csv`.
+    Tsv,
+    /// A JSON array with one object per line.
+    Json,
+    /// A Markdown table with the same columns as `csv`.
+    Markdown,
+}
+
+impl Output {
+    const fn export(self) -> Option<Export> {
+        match self {
+            Self::Csv => Some(Export::Csv),
+            Self::Tsv => Some(Export::Tsv),
+            Self::Json => Some(Export::Json),
+            Self::Markdown => Some(Export::Markdown),
+            _ => None,
+        }
+    }
+}
+
+fn parse_zone(zone: &str) -> Result<Zone, String> {
+    Zone::parse(zone).ok_or_else(|| format!("invalid zone `{zone}`"))
+}
+
+fn parse_format(format: &str) -> Result<DateFormat, String> {
+    DateFormat::parse(format).ok_or_else(|| format!("invalid datetime format `{format}`"))
+}
+
+fn system_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Evaluates the `--now` override against the real clock, so `now - 1d` is accepted too.
+fn pinned_now(now: Option<&str>, settings: &Settings) -> Result<Option<i64>, String> {
+    let Some(now) = now else {
+        return Ok(None);
+    };
+    let records = parser::parse_with(now, system_now(), settings.clone());
+    match records.first().map(|record| &record.expression) {
+        Some(Expression::Timestamp(timestamp)) => Ok(Some(*timestamp)),
+        _ => Err(format!("`{now}` is not a point in time")),
+    }
+}
+
+impl Cli {
+    fn settings(&self) -> Settings {
+        Settings {
+            zone: self.zone,
+            format: self.format.clone(),
+            now: None,
+        }
+    }
+}
+
+fn read_lines(cli: &Cli) -> io::Result<Vec<String>> {
+    let mut lines = cli.lines.clone();
+    let mut files = cli.files.clone();
+    if lines.is_empty() && files.is_empty() {
+        files.push("-".to_string());
+    }
+    for file in files {
+        let text = if file == "-" {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            text
+        } else {
+            fs::read_to_string(&file)
+                .map_err(|e| io::Error::new(e.kind(), format!("{file}: {e}")))?
+        };
+        lines.extend(text.lines().map(str::to_string));
+    }
+    Ok(lines)
+}
+
+fn run(cli: &Cli) -> Result<(), Box<dyn Error>> {
+    let settings = cli.settings();
+    let now = pinned_now(cli.now.as_deref(), &settings)?.unwrap_or_else(system_now);
+    let input = read_lines(cli)?.join("\n");
+    let sheet = Worksheet::with_settings(input, now, settings);
+    let mut out = io::stdout().lock();
+    match cli.output {
+        Output::Table => writeln!(out, "{sheet}")?,
+        Output::Relative => writeln!(out, "{}", sheet.to_relative_string(now))?,
+        Output::Datetime => {
+            for row in sheet.records.iter().flat_map(Record::rows) {
+                writeln!(out, "{}", row.to_datetime_string())?;
+            }
+        }
+        Output::Timestamp => {
+            for row in sheet.records.iter().flat_map(Record::rows) {
+                writeln!(out, "{}", row.to_timestamp_string())?;
+            }
+        }
+        output => {
+            if let Some(export) = output.export() {
+                write!(out, "{}", export.write(&sheet))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let settings = cli.settings();
+    let result = match &cli.command {
+        Some(Command::Annotate(annotate)) => annotate.run(&settings).map_err(Into::into),
+        Some(Command::Tui(tui)) => pinned_now(cli.now.as_deref(), &settings)
+            .map_err(Into::into)
+            .and_then(|now| tui.run(settings, now).map_err(Into::into)),
+        Some(Command::Repl(repl)) => pinned_now(cli.now.as_deref(), &settings)
+            .map_err(Into::into)
+            .and_then(|now| repl.run(settings, now, cli.output).map_err(Into::into)),
+        None => run(&cli),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("utc: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,370 @@
+//! `utc repl`: evaluate one line at a time.
+//!
+//! Every evaluated line becomes a numbered record, exactly like a line of the web worksheet,
+//! so later lines can refer to it as `#N` (or `$N`), and to the previous one as `_`.
+//! Lines starting with `:` are commands that change the session instead.
+
+use std::iter;
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor};
+use unix_time_calculator::complete;
+use unix_time_calculator::parser;
+use unix_time_calculator::{DateFormat, Export, Record, Row, Settings, Zone};
+
+use crate::{Output, pinned_now, system_now};
+
+const HELP: &str = "\
+Enter an expression to evaluate it. Results are numbered and can be referenced as #N or $N,
+and the previous result as _.
+
+:tz [ZONE]       show or set the zone, e.g. :tz UTC+5 or :tz Asia/Tokyo
+:format [FORMAT] show or set the datetime format: a preset such as iso or a strftime pattern
+:output [OUTPUT] show or set what is printed: table, datetime, timestamp, relative,
+                 or a row of csv, tsv, json or markdown
+:now [TIME]      show or pin the value of `now`; `:now clock` follows the system clock again
+:help            show this help";
+
+const COMMANDS: [&str; 5] = [":tz", ":format", ":output", ":now", ":help"];
+
+#[derive(Args)]
+pub struct Repl {
+    /// File to keep the history in. Defaults to `~/.utc_history`.
+    #[arg(long, value_name = "FILE")]
+    history: Option<PathBuf>,
+}
+
+impl Repl {
+    pub fn run(
+        &self,
+        settings: Settings,
+        now: Option<i64>,
+        output: Output,
+    ) -> rustyline::Result<()> {
+        let history = self.history.clone().or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".utc_history"))
+        });
+        let mut editor: Editor<Helper, FileHistory> = Editor::new()?;
+        editor.set_helper(Some(Helper));
+        if let Some(history) = &history {
+            // There is no history yet the first time around.
+            let _ = editor.load_history(history);
+        }
+
+        let mut session = Session::new(settings, now, output);
+        loop {
+            match editor.readline("utc> ") {
+                Ok(line) => {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    editor.add_history_entry(line.as_str())?;
+                    println!("{}", session.eval(&line));
+                }
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(e),
+            }
+        }
+        if let Some(history) = &history {
+            editor.save_history(history)?;
+        }
+        Ok(())
+    }
+}
+
+struct Session {
+    records: Vec<parser::Record>,
+    settings: Settings,
+    /// `now` pinned with `--now` or `:now`, or `None` to follow the system clock.
+    now: Option<i64>,
+    output: Output,
+}
+
+impl Session {
+    const fn new(settings: Settings, now: Option<i64>, output: Output) -> Self {
+        Self {
+            records: vec![],
+            settings,
+            now,
+            output,
+        }
+    }
+
+    fn eval(&mut self, line: &str) -> String {
+        let line = line.trim();
+        if let Some(command) = line.strip_prefix(':') {
+            return self.command(command);
+        }
+        let now = self.now.unwrap_or_else(system_now);
+        let record = parser::parse_next(line, &self.settings, now, &self.records);
+        self.settings = record.next_settings();
+        let result = Record::from(&record);
+        self.records.push(record);
+        // A series is shown on several rows, all but the first without the input or number.
+        let n = format!("#{}", self.records.len());
+        let labels = iter::once((n.as_str(), line)).chain(iter::repeat(("", "")));
+        let rows: Vec<_> = labels
+            .zip(result.rows())
+            .map(|((n, input), row)| self.row(n, input, row, now))
+            .collect();
+        rows.join("\n")
+    }
+
+    fn row(&self, n: &str, input: &str, result: &Record, now: i64) -> String {
+        let export = |export: Export| export.row(&Row::new(input, result));
+        match self.output {
+            Output::Table => format!(
+                "{n:<4} {:<26}  {}",
+                result.to_datetime_string(),
+                result.to_timestamp_string()
+            ),
+            Output::Datetime => format!("{n:<4} {}", result.to_datetime_string()),
+            Output::Timestamp => format!("{n:<4} {}", result.to_timestamp_string()),
+            Output::Relative => format!(
+                "{n:<4} {:<26}  {}",
+                result.to_datetime_string(),
+                result.to_relative_string(now)
+            )
+            .trim_end()
+            .to_string(),
+            Output::Csv => export(Export::Csv),
+            Output::Tsv => export(Export::Tsv),
+            Output::Json => export(Export::Json),
+            Output::Markdown => export(Export::Markdown),
+        }
+    }
+
+    fn command(&mut self, command: &str) -> String {
+        let (name, argument) = command
+            .split_once(' ')
+            .map_or((command, ""), |(name, argument)| (name, argument.trim()));
+        match (name, argument) {
+            ("tz", "") => self.settings.zone.to_string(),
+            ("tz", zone) => match Zone::parse(zone) {
+                Some(zone) => {
+                    self.settings.zone = zone;
+                    zone.to_string()
+                }
+                None => format!("invalid zone `{zone}`"),
+            },
+            ("format", "") => self.settings.format.name().to_string(),
+            ("format", format) => match DateFormat::parse(format) {
+                Some(format) => {
+                    self.settings.format = format;
+                    self.settings.format.name().to_string()
+                }
+                None => format!("invalid datetime format `{format}`"),
+            },
+            ("output", "") => self
+                .output
+                .to_possible_value()
+                .unwrap()
+                .get_name()
+                .to_string(),
+            ("output", output) => match Output::from_str(output, true) {
+                Ok(output) => {
+                    self.output = output;
+                    output.to_possible_value().unwrap().get_name().to_string()
+                }
+                Err(e) => e,
+            },
+            ("now", "") => self.show_now(),
+            ("now", "clock") => {
+                self.now = None;
+                self.show_now()
+            }
+            ("now", now) => match pinned_now(Some(now), &self.settings) {
+                Ok(now) => {
+                    self.now = now;
+                    self.show_now()
+                }
+                Err(e) => e,
+            },
+            ("help", _) => HELP.to_string(),
+            _ => format!("unknown command `:{name}`, try :help"),
+        }
+    }
+
+    fn show_now(&self) -> String {
+        let now = self.now.unwrap_or_else(system_now);
+        let now = Record::timestamp(now, self.settings.zone.offset_at(now))
+            .with_format(self.settings.format.clone());
+        match self.now {
+            Some(_) => format!("{} (pinned)", now.to_datetime_string()),
+            None => format!("{} (clock)", now.to_datetime_string()),
+        }
+    }
+}
+
+/// Completions for the word being typed, given the text before it on the line.
+fn completions(before: &str, word: &str) -> Vec<String> {
+    let candidates: Vec<String> = match before.trim_end() {
+        "" if word.starts_with(':') => COMMANDS.iter().map(|c| c.to_string()).collect(),
+        ":tz" => complete::offsets()
+            .chain(complete::zone_names().map(str::to_string))
+            .collect(),
+        ":format" => DateFormat::PRESETS
+            .iter()
+            .map(|format| format.name().to_string())
+            .collect(),
+        ":output" => Output::value_variants()
+            .iter()
+            .filter_map(|output| Some(output.to_possible_value()?.get_name().to_string()))
+            .collect(),
+        ":now" => vec!["now".to_string(), "clock".to_string()],
+        _ => {
+            return complete::complete(before, word, &[])
+                .into_iter()
+                .map(|completion| completion.text)
+                .collect();
+        }
+    };
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(word))
+        .collect()
+}
+
+struct Helper;
+
+impl Completer for Helper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        let start = complete::word_start(line);
+        Ok((start, completions(&line[..start], &line[start..])))
+    }
+}
+
+impl Hinter for Helper {
+    type Hint = String;
+}
+
+impl Highlighter for Helper {}
+
+impl Validator for Helper {}
+
+impl rustyline::Helper for Helper {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn records() {
+        let mut session = Session::new(Settings::default(), Some(0), Output::Table);
+        assert_eq!(
+            session.eval("1748000000"),
+            "#1   2025-05-23 11:33:20 +00:00  1748000000"
+        );
+        assert_eq!(
+            session.eval("#UTC+2"),
+            "#2   UTC+02:00                   UTC+02:00"
+        );
+        assert_eq!(session.eval("now"), "#3   1970-01-01 02:00:00 +02:00  0");
+        assert_eq!(
+            session.eval("#1 - _"),
+            "#4   20231d11h33m20s             1748000000"
+        );
+        assert_eq!(
+            session.eval("$4 + nonsense"),
+            "#5   ...                         ..."
+        );
+        assert_eq!(
+            session.eval("series(#1, 1h, 2)"),
+            concat!(
+                "#6   2025-05-23 13:33:20 +02:00  1748000000\n",
+                "     2025-05-23 14:33:20 +02:00  1748003600"
+            )
+        );
+    }
+
+    #[test]
+    fn commands() {
+        let mut session = Session::new(Settings::default(), None, Output::Table);
+        assert_eq!(session.eval(":tz +05:30"), "UTC+05:30");
+        assert_eq!(session.eval(":tz Mars"), "invalid zone `Mars`");
+        assert_eq!(session.eval(":tz"), "UTC+05:30");
+        assert_eq!(
+            session.eval(":now 0"),
+            "1970-01-01 05:30:00 +05:30 (pinned)"
+        );
+        assert_eq!(session.eval(":output datetime"), "datetime");
+        assert_eq!(session.eval("now + 1h"), "#1   1970-01-01 06:30:00 +05:30");
+        assert_eq!(session.eval(":format ISO"), "iso");
+        assert_eq!(session.eval("#1"), "#2   1970-01-01T06:30:00+05:30");
+        assert_eq!(session.eval("#FORMAT %H:%M"), "#3   FORMAT %H:%M");
+        assert_eq!(session.eval(":format"), "%H:%M");
+        assert_eq!(
+            session.eval(":format isoo"),
+            "invalid datetime format `isoo`"
+        );
+        assert_eq!(session.eval(":output relative"), "relative");
+        assert_eq!(
+            session.eval("now - 1d"),
+            "#4   05:30                       1d ago"
+        );
+        assert!(session.eval(":now clock").ends_with("(clock)"));
+        assert_eq!(session.eval(":output csv"), "csv");
+        assert_eq!(session.eval("#1"), "#1,06:30,3600,UTC+05:30,");
+        assert!(session.eval(":output xml").starts_with("invalid variant"));
+        assert_eq!(
+            session.eval(":bogus"),
+            "unknown command `:bogus`, try :help"
+        );
+    }
+
+    #[test]
+    fn named_zones() {
+        let mut session = Session::new(Settings::default(), None, Output::Datetime);
+        assert_eq!(session.eval(":tz Asia/Tokyo"), "Asia/Tokyo");
+        assert_eq!(
+            session.eval("1748000000"),
+            "#1   2025-05-23 20:33:20 +09:00"
+        );
+        assert_eq!(session.eval(":tz Europe/Paris"), "Europe/Paris");
+        assert_eq!(
+            session.eval("1735689600"),
+            "#2   2025-01-01 01:00:00 +01:00"
+        );
+    }
+
+    #[test]
+    fn completion() {
+        assert_eq!(completions("", ":f"), vec![":format"]);
+        assert_eq!(
+            completions(":tz ", "UTC+1"),
+            vec!["UTC+1", "UTC+10", "UTC+11", "UTC+12", "UTC+13", "UTC+14"]
+        );
+        assert_eq!(completions(":tz ", "Asia/Tok"), vec!["Asia/Tokyo"]);
+        assert_eq!(
+            completions(":output ", "t"),
+            vec!["table", "timestamp", "tsv"]
+        );
+        assert_eq!(completions(":format ", "rfc"), vec!["rfc2822", "rfc3339ms"]);
+        assert_eq!(
+            completions("", "#UTC-1"),
+            vec!["#UTC-1", "#UTC-10", "#UTC-11", "#UTC-12"]
+        );
+        assert_eq!(completions("1h + ", "n"), vec!["now"]);
+        assert_eq!(completions("2h in ", "min"), vec!["min", "minutes"]);
+        assert_eq!(
+            completions("now in ", "Asia/To"),
+            vec!["Asia/Tokyo", "Asia/Tomsk"]
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! `utc tui`: a full-screen worksheet laid out like the web page, with line numbers, the
+//! editable input and the datetime and timestamp columns side by side.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Args;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use tui_textarea::TextArea;
+use unix_time_calculator::{Settings, Worksheet};
+
+use crate::system_now;
+
+/// How often the worksheet is re-evaluated while no key is pressed, so `now` keeps ticking.
+const TICK: Duration = Duration::from_secs(1);
+
+#[derive(Args)]
+pub struct Tui {
+    /// File to load into the editor.
+    file: Option<PathBuf>,
+}
+
+impl Tui {
+    pub fn run(&self, settings: Settings, now: Option<i64>) -> io::Result<()> {
+        let lines = match &self.file {
+            Some(file) => fs::read_to_string(file)?
+                .lines()
+                .map(str::to_string)
+                .collect(),
+            None => vec![],
+        };
+        let mut app = App::new(lines, settings, now);
+        let mut terminal = ratatui::init();
+        let result = app.run(&mut terminal);
+        ratatui::restore();
+        result
+    }
+}
+
+struct App<'a> {
+    editor: TextArea<'a>,
+    sheet: Worksheet,
+    settings: Settings,
+    /// `now` pinned with `--now`, or `None` to follow the system clock.
+    now: Option<i64>,
+    /// First visible line. Follows the editor's own scrolling, which keeps the cursor in
+    /// view with the smallest possible jump, so the result columns stay aligned with it.
+    top: usize,
+}
+
+impl App<'_> {
+    fn new(lines: Vec<String>, settings: Settings, now: Option<i64>) -> Self {
+        let mut editor = TextArea::new(lines);
+        editor.set_cursor_line_style(Style::default());
+        let mut app = Self {
+            editor,
+            sheet: Worksheet::empty(),
+            settings,
+            now,
+            top: 0,
+        };
+        app.evaluate();
+        app
+    }
+
+    fn evaluate(&mut self) {
+        let input = self.editor.lines().join("\n");
+        let now = self.now.unwrap_or_else(system_now);
+        self.sheet.update(input, now, self.settings.clone());
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if event::poll(TICK)?
+                && let Event::Key(key) = event::read()?
+            {
+                let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                match key.code {
+                    KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('c' | 'q') if ctrl => return Ok(()),
+                    _ if key.kind == KeyEventKind::Press => {
+                        self.editor.input(key);
+                    }
+                    _ => {}
+                }
+            }
+            self.evaluate();
+        }
+    }
+
+    fn scroll(&mut self, height: usize) {
+        let (row, _) = self.editor.cursor();
+        if row < self.top {
+            self.top = row;
+        } else if height > 0 && row >= self.top + height {
+            self.top = row + 1 - height;
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [title, body, help] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        frame.render_widget(Line::from("Unix Time Calculator").bold(), title);
+        frame.render_widget(
+            Line::from("Esc quit · #UTC+N set zone · #FORMAT iso set format · #N reference a line")
+                .dark_gray(),
+            help,
+        );
+
+        self.scroll(body.height as usize);
+        let records = &self.sheet.records;
+        let rows = self.top..records.len().min(self.top + body.height as usize);
+        let timestamps: Vec<_> = records[rows.clone()]
+            .iter()
+            .map(|record| record.to_timestamp_string())
+            .collect();
+        // Wide enough for the longest timestamp on screen, such as an interval's, and
+        // the separator before it.
+        let widest = timestamps.iter().map(|t| t.chars().count()).max();
+        let digits = self.sheet.records.len().to_string().len() as u16;
+        let [numbers, input, datetime, timestamp] = Layout::horizontal([
+            Constraint::Length(digits),
+            Constraint::Min(10),
+            Constraint::Length(28),
+            Constraint::Length((widest.unwrap_or(0) as u16 + 1).max(16)),
+        ])
+        .areas(body);
+        let line_numbers: Vec<_> = rows
+            .clone()
+            .map(|i| Line::from((i + 1).to_string()))
+            .collect();
+        frame.render_widget(
+            Paragraph::new(line_numbers)
+                .right_aligned()
+                .fg(Color::Green),
+            numbers,
+        );
+
+        frame.render_widget(&self.editor, separator().inner(input));
+        frame.render_widget(separator(), input);
+
+        let datetimes = records[rows.clone()]
+            .iter()
+            .map(|record| Line::from(record.to_datetime_string()))
+            .collect::<Vec<_>>();
+        frame.render_widget(Paragraph::new(datetimes).block(separator()), datetime);
+
+        let timestamps = timestamps.into_iter().map(Line::from).collect::<Vec<_>>();
+        frame.render_widget(Paragraph::new(timestamps).block(separator()), timestamp);
+    }
+}
+
+/// The thin rule drawn between columns, like the borders in the web page.
+fn separator() -> Block<'static> {
+    Block::new()
+        .borders(Borders::LEFT)
+        .border_style(Style::default().dark_gray())
+}
+
+#[cfg(test)]
+mod test {
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+
+    fn screen(app: &mut App, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(80, height)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..80)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn columns() {
+        let lines = vec![
+            "#UTC+1".to_string(),
+            "1748000000".to_string(),
+            "2h".to_string(),
+        ];
+        let mut app = App::new(lines, Settings::default(), Some(0));
+        let screen = screen(&mut app, 6);
+        assert_eq!(
+            screen[1],
+            "1│#UTC+1                            │UTC+01:00                  │UTC+01:00"
+        );
+        assert_eq!(
+            screen[2],
+            "2│1748000000                        │2025-05-23 12:33:20 +01:00 │1748000000"
+        );
+        assert_eq!(
+            screen[3],
+            "3│2h                                │2h                         │7200"
+        );
+    }
+
+    #[test]
+    fn long_timestamps() {
+        // A long timestamp takes room from the input rather than being cut off.
+        let lines = vec!["1748000000 .. 100000000000".to_string()];
+        let mut app = App::new(lines, Settings::default(), Some(0));
+        assert_eq!(
+            screen(&mut app, 3)[1],
+            "1│1748000000 .. 10000000000│2025-05-23 11:33:20 +00:00 │1748000000..100000000000"
+        );
+    }
+
+    #[test]
+    fn scrolls_with_the_cursor() {
+        let lines = (0..10).map(|i| i.to_string()).collect();
+        let mut app = App::new(lines, Settings::default(), Some(0));
+        for _ in 0..6 {
+            app.editor.move_cursor(tui_textarea::CursorMove::Down);
+        }
+        let screen = screen(&mut app, 6);
+        assert!(screen[1].starts_with(" 4│3 "));
+        assert!(screen[4].starts_with(" 7│6 "));
+    }
+}
@@ -0,0 +1,247 @@
+//! Context-aware completion of the word being typed, shared by the web editor and the REPL.
+//!
+//! Offers zone names, formats and units after `in`/`as`, headers at the start of a line,
+//! unit suffixes after numbers, `now` and `series(`, operators such as `overlaps` after a value,
+//! and `#N` references with a preview of that line.
+
+use chrono_tz::TZ_VARIANTS;
+
+use crate::format::{DateFormat, Record};
+
+/// Number suffixes understood by durations, with what they mean.
+const DURATION_UNITS: [(&str, &str); 5] = [
+    ("d", "days"),
+    ("h", "hours"),
+    ("m", "minutes"),
+    ("s", "seconds"),
+    ("ms", "milliseconds"),
+];
+
+/// Words that evaluate on their own, with what they mean.
+const FUNCTIONS: [(&str, &str); 2] = [
+    ("now", "current time"),
+    ("series(", "start, step, count: datetimes step apart"),
+];
+
+/// Words that go between two values, with what they give.
+const OPERATORS: [(&str, &str); 7] = [
+    (
+        "contains",
+        "whether the interval contains a datetime or interval",
+    ),
+    ("overlaps", "whether two intervals overlap"),
+    ("intersect", "the interval both cover"),
+    ("union", "the interval covering both"),
+    ("gap", "the time between two intervals"),
+    ("every", "a series of datetimes a step apart"),
+    ("until", "where a series stops"),
+];
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Completion {
+    /// What replaces the word being typed.
+    pub text: String,
+    /// A short description, or the value of the line a reference points to.
+    pub detail: String,
+}
+
+impl Completion {
+    fn new(text: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Where the word being typed starts in `line`, the text before the cursor.
+pub fn word_start(line: &str) -> usize {
+    line.rfind([' ', '(']).map_or(0, |i| i + 1)
+}
+
+/// Fixed offsets from `UTC-12` to `UTC+14`, starting with plain `UTC`.
+pub fn offsets() -> impl Iterator<Item = String> {
+    let east = (0..=14).map(|h| format!("UTC+{h}"));
+    let west = (1..=12).map(|h| format!("UTC-{h}"));
+    std::iter::once("UTC".to_string()).chain(east).chain(west)
+}
+
+/// IANA zone names such as `Asia/Tokyo`.
+pub fn zone_names() -> impl Iterator<Item = &'static str> {
+    TZ_VARIANTS.iter().map(|tz| tz.name())
+}
+
+/// Completions for `word`, given the text `before` it on the line and the records of the
+/// lines above, which `#N` references preview.
+pub fn complete(before: &str, word: &str, records: &[Record]) -> Vec<Completion> {
+    let before = before.trim_end();
+    let candidates: Vec<Completion> = if before.ends_with(" in") || before.ends_with(" as") {
+        conversions()
+    } else if before == "#FORMAT" {
+        DateFormat::PRESETS
+            .iter()
+            .map(|format| Completion::new(format.name(), "datetime format"))
+            .collect()
+    } else if word.starts_with(['#', '$']) {
+        references(word, records).chain(headers(before)).collect()
+    } else if let Some(number) = number_prefix(word) {
+        DURATION_UNITS
+            .iter()
+            .map(|(unit, name)| Completion::new(format!("{number}{unit}"), *name))
+            .collect()
+    } else if before.is_empty() || before.ends_with(['+', '-', '(', '.']) {
+        FUNCTIONS
+            .iter()
+            .map(|(name, detail)| Completion::new(*name, *detail))
+            .collect()
+    } else {
+        FUNCTIONS
+            .iter()
+            .chain(&OPERATORS)
+            .map(|(name, detail)| Completion::new(*name, *detail))
+            .collect()
+    };
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.text.starts_with(word))
+        .collect()
+}
+
+/// Zones, datetime formats and units, as accepted after `in` and `as`.
+fn conversions() -> Vec<Completion> {
+    let units = crate::Unit::ALL
+        .iter()
+        .flat_map(|(_, names)| names.iter().map(|name| Completion::new(*name, "unit")));
+    let presets = DateFormat::PRESETS
+        .iter()
+        .map(|format| Completion::new(format.name(), "datetime format"));
+    let named = zone_names().map(|name| Completion::new(name, "zone"));
+    let fixed = offsets().map(|offset| Completion::new(offset, "UTC offset"));
+    units
+        .chain([Completion::new("filetime", "Windows FILETIME")])
+        .chain(presets)
+        .chain(named)
+        .chain(fixed)
+        .collect()
+}
+
+/// References that stay on the same line when lines are inserted above.
+const RELATIVE: [(&str, &str); 4] = [
+    ("#-1", "line above"),
+    ("#prev", "closest value above"),
+    ("#first", "first value"),
+    ("#last-timestamp", "closest datetime above"),
+];
+
+/// `#1` to `#N` for the lines above, in the style (`#` or `$`) of the word being typed,
+/// then the relative references.
+fn references<'a>(word: &str, records: &'a [Record]) -> impl Iterator<Item = Completion> + 'a {
+    let sigil = if word.starts_with('$') { "$" } else { "#" };
+    let absolute = records.iter().enumerate().map(move |(i, record)| {
+        Completion::new(format!("{sigil}{}", i + 1), record.to_datetime_string())
+    });
+    let relative = RELATIVE
+        .iter()
+        .filter(move |_| !records.is_empty())
+        .map(|(reference, detail)| Completion::new(*reference, *detail));
+    absolute.chain(relative)
+}
+
+/// Headers, which only make sense as the first word of a line.
+fn headers(before: &str) -> Vec<Completion> {
+    if !before.is_empty() {
+        return vec![];
+    }
+    // Offset headers always carry a sign, so a bare `#UTC` is not offered.
+    let offsets = offsets()
+        .skip(1)
+        .map(|offset| Completion::new(format!("#{offset}"), "zone for the lines below"));
+    offsets
+        .chain([
+            Completion::new("#FORMAT", "datetime format for the lines below"),
+            Completion::new("#NOW", "pin now for the lines below"),
+        ])
+        .collect()
+}
+
+/// The number at the start of `word`, if the rest could still become a unit suffix.
+fn number_prefix(word: &str) -> Option<&str> {
+    let end = word
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(word.len());
+    let number = &word[..end];
+    let suffix = &word[end..];
+    let possible = DURATION_UNITS
+        .iter()
+        .any(|(unit, _)| unit.starts_with(suffix));
+    (number.starts_with(|c: char| c.is_ascii_digit()) && possible).then_some(number)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Worksheet;
+
+    fn texts(completions: Vec<Completion>) -> Vec<String> {
+        completions.into_iter().map(|c| c.text).collect()
+    }
+
+    #[test]
+    fn contexts() {
+        assert_eq!(texts(complete("1h + ", "n", &[])), vec!["now"]);
+        assert_eq!(
+            texts(complete("2h in ", "min", &[])),
+            vec!["min", "minutes"]
+        );
+        assert_eq!(
+            texts(complete("now in ", "Asia/To", &[])),
+            vec!["Asia/Tokyo", "Asia/Tomsk"]
+        );
+        assert_eq!(
+            texts(complete("#FORMAT ", "rfc", &[])),
+            vec!["rfc2822", "rfc3339ms"]
+        );
+        assert_eq!(
+            texts(complete("", "#UTC-1", &[])),
+            vec!["#UTC-1", "#UTC-10", "#UTC-11", "#UTC-12"]
+        );
+        assert_eq!(texts(complete("", "#N", &[])), vec!["#NOW"]);
+        assert_eq!(texts(complete("now - ", "#N", &[])), Vec::<String>::new());
+        assert_eq!(texts(complete("#1..#2 ", "o", &[])), vec!["overlaps"]);
+        assert_eq!(texts(complete("1h + ", "o", &[])), Vec::<String>::new());
+        assert_eq!(texts(complete("now ", "ev", &[])), vec!["every"]);
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(
+            texts(complete("now + ", "90", &[])),
+            vec!["90d", "90h", "90m", "90s", "90ms"]
+        );
+        assert_eq!(texts(complete("", "1.5m", &[])), vec!["1.5m", "1.5ms"]);
+        assert_eq!(texts(complete("", "1x", &[])), Vec::<String>::new());
+    }
+
+    #[test]
+    fn references() {
+        let sheet = Worksheet::new("1748000000\n2h".to_string(), 0);
+        assert_eq!(
+            complete("now - ", "#", &sheet.records),
+            vec![
+                Completion::new("#1", "2025-05-23 11:33:20 +00:00"),
+                Completion::new("#2", "2h"),
+                Completion::new("#-1", "line above"),
+                Completion::new("#prev", "closest value above"),
+                Completion::new("#first", "first value"),
+                Completion::new("#last-timestamp", "closest datetime above"),
+            ]
+        );
+        assert_eq!(
+            texts(complete("now - ", "#p", &sheet.records)),
+            vec!["#prev"]
+        );
+        assert_eq!(texts(complete("", "$2", &sheet.records)), vec!["$2"]);
+        assert_eq!(word_start("now - #"), 6);
+        assert_eq!(word_start("(#1"), 1);
+    }
+}
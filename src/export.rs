@@ -0,0 +1,304 @@
+//! Worksheets written out as CSV, TSV, JSON or Markdown, for pasting into
+//! spreadsheets, tickets and scripts.
+//!
+//! Every line of the worksheet becomes one row with the columns of [`Row`].
+
+use chrono::Offset;
+
+use crate::format::{Record, Worksheet};
+
+/// The error given for lines that could not be evaluated.
+const INVALID: &str = "invalid input";
+
+/// The error given for lines whose references go round in a circle.
+const CIRCULAR: &str = "circular reference";
+
+/// The error given for lines that depend on a circle of references without being in it.
+const DEPENDS_ON_CIRCULAR: &str = "depends on a circular reference";
+
+/// One line of a worksheet, with the columns shown on the page.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Row {
+    pub input: String,
+    pub datetime: String,
+    pub timestamp: String,
+    /// The UTC offset of a datetime, e.g. `UTC+01:00`. Empty for other lines.
+    pub zone: String,
+    /// Why the line has no value. Empty for valid and blank lines.
+    pub error: String,
+}
+
+impl Row {
+    pub fn new(input: &str, record: &Record) -> Self {
+        let input = input.trim();
+        let error = match record {
+            Record::None if !input.is_empty() => INVALID,
+            Record::Error(error) => error,
+            _ => "",
+        };
+        let (datetime, timestamp) = match record {
+            Record::None | Record::Error(_) => ("".to_string(), "".to_string()),
+            _ => (record.to_datetime_string(), record.to_timestamp_string()),
+        };
+        let zone = match record {
+            Record::DateTime(datetime, _) | Record::Interval(datetime, ..) => {
+                format!("UTC{}", datetime.offset().fix())
+            }
+            _ => "".to_string(),
+        };
+        Self {
+            input: input.to_string(),
+            datetime,
+            timestamp,
+            zone,
+            error: error.to_string(),
+        }
+    }
+
+    fn columns(&self) -> [&str; 5] {
+        [
+            &self.input,
+            &self.datetime,
+            &self.timestamp,
+            &self.zone,
+            &self.error,
+        ]
+    }
+}
+
+impl Worksheet {
+    pub fn rows(&self) -> Vec<Row> {
+        self.expanded()
+            .map(|(line, input, record)| {
+                let error = if self.cycles.contains(&line) {
+                    CIRCULAR
+                } else if self.circular.contains(&line) {
+                    DEPENDS_ON_CIRCULAR
+                } else {
+                    return Row::new(input, record);
+                };
+                Row {
+                    error: error.to_string(),
+                    ..Row::new(input, record)
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Export {
+    Csv,
+    Tsv,
+    Json,
+    Markdown,
+}
+
+impl Export {
+    pub const ALL: [Self; 4] = [Self::Csv, Self::Tsv, Self::Json, Self::Markdown];
+
+    const COLUMNS: [&str; 5] = ["input", "datetime", "timestamp", "zone", "error"];
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Tsv => "tsv",
+            Self::Json => "json",
+            Self::Markdown => "markdown",
+        }
+    }
+
+    pub const fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            _ => self.name(),
+        }
+    }
+
+    pub const fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv",
+            Self::Tsv => "text/tab-separated-values",
+            Self::Json => "application/json",
+            Self::Markdown => "text/markdown",
+        }
+    }
+
+    /// The whole worksheet, ending with a newline.
+    pub fn write(&self, sheet: &Worksheet) -> String {
+        let rows: Vec<_> = sheet.rows().iter().map(|row| self.row(row)).collect();
+        match self {
+            Self::Json if rows.is_empty() => "[]\n".to_string(),
+            Self::Json => format!("[\n  {}\n]\n", rows.join(",\n  ")),
+            _ => {
+                let mut text = self.header();
+                for row in rows {
+                    text.push_str(&row);
+                    text.push('\n');
+                }
+                text
+            }
+        }
+    }
+
+    /// The lines written before the rows; empty for JSON.
+    pub fn header(&self) -> String {
+        match self {
+            Self::Csv => format!("{}\n", Self::COLUMNS.join(",")),
+            Self::Tsv => format!("{}\n", Self::COLUMNS.join("\t")),
+            Self::Json => "".to_string(),
+            Self::Markdown => format!(
+                "| {} |\n|{}\n",
+                Self::COLUMNS.join(" | "),
+                "---|".repeat(Self::COLUMNS.len())
+            ),
+        }
+    }
+
+    /// A single row, without a newline. JSON rows are objects, one per line, so a
+    /// stream of them is valid JSON Lines.
+    pub fn row(&self, row: &Row) -> String {
+        let columns = row.columns();
+        match self {
+            Self::Csv => columns.map(csv_field).join(","),
+            Self::Tsv => columns
+                .map(|c| c.replace(['\t', '\n', '\r'], " "))
+                .join("\t"),
+            Self::Json => {
+                let fields: Vec<_> = Self::COLUMNS
+                    .iter()
+                    .zip(columns)
+                    .map(|(name, value)| format!("\"{name}\": {}", json_string(value)))
+                    .collect();
+                format!("{{{}}}", fields.join(", "))
+            }
+            Self::Markdown => {
+                let cells = columns.map(|c| c.replace('|', "\\|").replace(['\n', '\r'], " "));
+                format!("| {} |", cells.join(" | "))
+            }
+        }
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c < ' ' => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sheet() -> Worksheet {
+        Worksheet::new("#UTC+1\n1748000000\n1h30m\n\n'a, \"b\"' | x".to_string(), 0)
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(
+            Export::Csv.write(&sheet()),
+            concat!(
+                "input,datetime,timestamp,zone,error\n",
+                "#UTC+1,UTC+01:00,UTC+01:00,,\n",
+                "1748000000,2025-05-23 12:33:20 +01:00,1748000000,UTC+01:00,\n",
+                "1h30m,1h30m,5400,,\n",
+                ",,,,\n",
+                "\"'a, \"\"b\"\"' | x\",,,,invalid input\n",
+            )
+        );
+    }
+
+    #[test]
+    fn tsv_and_markdown() {
+        let sheet = Worksheet::new("1748000000".to_string(), 0);
+        assert_eq!(
+            Export::Tsv.write(&sheet),
+            "input\tdatetime\ttimestamp\tzone\terror\n\
+             1748000000\t2025-05-23 11:33:20 +00:00\t1748000000\tUTC+00:00\t\n"
+        );
+        assert_eq!(
+            Export::Tsv.write(&Worksheet::new("#2\n#1\n#1 + 1h".to_string(), 0)),
+            "input\tdatetime\ttimestamp\tzone\terror\n\
+             #2\t\t\t\tcircular reference\n\
+             #1\t\t\t\tcircular reference\n\
+             #1 + 1h\t\t\t\tdepends on a circular reference\n"
+        );
+        assert_eq!(
+            Export::Tsv.write(&Worksheet::new("1 + 2\nnow - 1h - now".to_string(), 0)),
+            "input\tdatetime\ttimestamp\tzone\terror\n\
+             1 + 2\t3\t3\t\t\n\
+             now - 1h - now\t-1h\t-3600\t\t\n"
+        );
+        assert_eq!(
+            Export::Tsv.write(&Worksheet::new("series(0, 1h, 2)\n#1".to_string(), 0)),
+            "input\tdatetime\ttimestamp\tzone\terror\n\
+             series(0, 1h, 2)\t1970-01-01 00:00:00 +00:00\t0\tUTC+00:00\t\n\
+             \t1970-01-01 01:00:00 +00:00\t3600\tUTC+00:00\t\n\
+             #1\t1970-01-01 00:00:00 +00:00\t0\tUTC+00:00\t\n\
+             \t1970-01-01 01:00:00 +00:00\t3600\tUTC+00:00\t\n"
+        );
+        assert_eq!(
+            Export::Tsv.write(&Worksheet::new("1h - now".to_string(), 0)),
+            "input\tdatetime\ttimestamp\tzone\terror\n\
+             1h - now\t\t\t\tcannot subtract a timestamp from a duration\n"
+        );
+        assert_eq!(
+            Export::Markdown.write(&Worksheet::new("a|b".to_string(), 0)),
+            concat!(
+                "| input | datetime | timestamp | zone | error |\n",
+                "|---|---|---|---|---|\n",
+                "| a\\|b |  |  |  | invalid input |\n",
+            )
+        );
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(
+            Export::Json.write(&sheet()),
+            concat!(
+                "[\n",
+                "  {\"input\": \"#UTC+1\", \"datetime\": \"UTC+01:00\", \"timestamp\": \"UTC+01:00\", \"zone\": \"\", \"error\": \"\"},\n",
+                "  {\"input\": \"1748000000\", \"datetime\": \"2025-05-23 12:33:20 +01:00\", \"timestamp\": \"1748000000\", \"zone\": \"UTC+01:00\", \"error\": \"\"},\n",
+                "  {\"input\": \"1h30m\", \"datetime\": \"1h30m\", \"timestamp\": \"5400\", \"zone\": \"\", \"error\": \"\"},\n",
+                "  {\"input\": \"\", \"datetime\": \"\", \"timestamp\": \"\", \"zone\": \"\", \"error\": \"\"},\n",
+                "  {\"input\": \"'a, \\\"b\\\"' | x\", \"datetime\": \"\", \"timestamp\": \"\", \"zone\": \"\", \"error\": \"invalid input\"}\n",
+                "]\n",
+            )
+        );
+        // Every kind of value has its timestamp as a string, so the field has one type.
+        let sheet = Worksheet::new("1 + 2\n1748000000 .. 1h\nseries(0, 1h, 2)".to_string(), 0);
+        assert_eq!(
+            Export::Json.write(&sheet),
+            concat!(
+                "[\n",
+                "  {\"input\": \"1 + 2\", \"datetime\": \"3\", \"timestamp\": \"3\", \"zone\": \"\", \"error\": \"\"},\n",
+                "  {\"input\": \"1748000000 .. 1h\", \"datetime\": \"2025-05-23 11:33:20 +00:00 .. 2025-05-23 12:33:20 +00:00\", \"timestamp\": \"1748000000..1748003600\", \"zone\": \"UTC+00:00\", \"error\": \"\"},\n",
+                "  {\"input\": \"series(0, 1h, 2)\", \"datetime\": \"1970-01-01 00:00:00 +00:00\", \"timestamp\": \"0\", \"zone\": \"UTC+00:00\", \"error\": \"\"},\n",
+                "  {\"input\": \"\", \"datetime\": \"1970-01-01 01:00:00 +00:00\", \"timestamp\": \"3600\", \"zone\": \"UTC+00:00\", \"error\": \"\"}\n",
+                "]\n",
+            )
+        );
+    }
+}
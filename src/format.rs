@@ -0,0 +1,940 @@
+use std::fmt::{self, Display, Write};
+
+use chrono::format::StrftimeItems;
+use chrono::{DateTime, Duration, FixedOffset, SecondsFormat, Utc};
+
+use crate::parser::{self, Expression, Settings, Zone};
+
+/// How the datetime column is written, chosen with a `#FORMAT` header.
+#[derive(Clone, PartialEq, Debug, Default)]
+#[non_exhaustive]
+pub enum DateFormat {
+    /// `2024-01-15 12:00:00 +05:00`
+    #[default]
+    Default,
+    /// `2024-01-15T12:00:00+05:00`
+    Iso,
+    /// `Mon, 15 Jan 2024 12:00:00 +0500`
+    Rfc2822,
+    /// `2024-01-15T12:00:00.000+05:00`
+    Rfc3339Ms,
+    /// `Mon, 15 Jan 2024 07:00:00 GMT`, always in UTC
+    Http,
+    /// `2024-01-15`
+    Date,
+    /// `12:00:00`
+    Time,
+    /// `Monday`
+    Weekday,
+    /// Any `strftime` pattern, e.g. `%d/%m/%Y %H:%M`
+    Strftime(String),
+}
+
+impl DateFormat {
+    /// The named presets, in the order they are listed in the help.
+    pub const PRESETS: [Self; 8] = [
+        Self::Default,
+        Self::Iso,
+        Self::Rfc2822,
+        Self::Rfc3339Ms,
+        Self::Http,
+        Self::Date,
+        Self::Time,
+        Self::Weekday,
+    ];
+
+    /// Parses a preset name or a `strftime` pattern. Patterns must contain at least one
+    /// `%` specifier, so a misspelt preset is not taken for literal text, and must format
+    /// a datetime: some specifiers, such as `%#z`, are only understood when parsing.
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        if let Some(preset) = Self::PRESETS
+            .into_iter()
+            .find(|preset| preset.name().eq_ignore_ascii_case(input))
+        {
+            return Some(preset);
+        }
+        let items = StrftimeItems::new(input);
+        let datetime = DateTime::UNIX_EPOCH.fixed_offset();
+        let valid = input.contains('%')
+            && write!(String::new(), "{}", datetime.format_with_items(items)).is_ok();
+        valid.then(|| Self::Strftime(input.to_string()))
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Default => "default",
+            Self::Iso => "iso",
+            Self::Rfc2822 => "rfc2822",
+            Self::Rfc3339Ms => "rfc3339ms",
+            Self::Http => "http",
+            Self::Date => "date",
+            Self::Time => "time",
+            Self::Weekday => "weekday",
+            Self::Strftime(format) => format,
+        }
+    }
+
+    pub fn format(&self, datetime: &DateTime<FixedOffset>) -> String {
+        match self {
+            Self::Default => datetime.to_string(),
+            Self::Iso => datetime.to_rfc3339_opts(SecondsFormat::Secs, false),
+            Self::Rfc2822 => datetime.to_rfc2822(),
+            Self::Rfc3339Ms => datetime.to_rfc3339_opts(SecondsFormat::Millis, false),
+            Self::Http => datetime
+                .with_timezone(&Utc)
+                .format("%a, %d %b %Y %H:%M:%S GMT")
+                .to_string(),
+            Self::Date => datetime.format("%Y-%m-%d").to_string(),
+            Self::Time => datetime.format("%H:%M:%S").to_string(),
+            Self::Weekday => datetime.format("%A").to_string(),
+            Self::Strftime(format) => datetime.format(format).to_string(),
+        }
+    }
+}
+
+/// A unit that durations are measured in, and timestamps counted in since the epoch.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Unit {
+    Nanoseconds,
+    Microseconds,
+    Milliseconds,
+    Seconds,
+    Minutes,
+    Hours,
+    Days,
+    Weeks,
+}
+
+impl Unit {
+    /// Every unit with the names it is written as.
+    pub const ALL: [(Self, &'static [&'static str]); 8] = [
+        (Self::Nanoseconds, &["ns", "nanoseconds"]),
+        (Self::Microseconds, &["us", "microseconds"]),
+        (Self::Milliseconds, &["ms", "milliseconds"]),
+        (Self::Seconds, &["s", "sec", "seconds"]),
+        (Self::Minutes, &["m", "min", "minutes"]),
+        (Self::Hours, &["h", "hours"]),
+        (Self::Days, &["d", "days"]),
+        (Self::Weeks, &["w", "weeks"]),
+    ];
+
+    /// The long name of the unit, such as `minutes`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Nanoseconds => "nanoseconds",
+            Self::Microseconds => "microseconds",
+            Self::Milliseconds => "milliseconds",
+            Self::Seconds => "seconds",
+            Self::Minutes => "minutes",
+            Self::Hours => "hours",
+            Self::Days => "days",
+            Self::Weeks => "weeks",
+        }
+    }
+
+    pub fn parse(input: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|(_, names)| names.contains(&input))
+            .map(|(unit, _)| unit)
+    }
+
+    const fn nanoseconds(self) -> i128 {
+        match self {
+            Self::Nanoseconds => 1,
+            Self::Microseconds => 1_000,
+            Self::Milliseconds => 1_000_000,
+            Self::Seconds => 1_000_000_000,
+            Self::Minutes => 60 * 1_000_000_000,
+            Self::Hours => 60 * 60 * 1_000_000_000,
+            Self::Days => 24 * 60 * 60 * 1_000_000_000,
+            Self::Weeks => 7 * 24 * 60 * 60 * 1_000_000_000,
+        }
+    }
+
+    /// `nanoseconds` expressed in this unit, without a fraction when it divides evenly.
+    fn format(self, nanoseconds: i128) -> String {
+        let unit = self.nanoseconds();
+        if nanoseconds % unit == 0 {
+            (nanoseconds / unit).to_string()
+        } else {
+            (nanoseconds as f64 / unit as f64).to_string()
+        }
+    }
+}
+
+/// A trailing `as …` or `in …` that changes how a single line is displayed.
+#[derive(Clone, PartialEq, Debug)]
+#[non_exhaustive]
+pub enum Conversion {
+    /// Show a datetime in another zone.
+    Zone(Zone),
+    /// Show a datetime in another format.
+    Format(DateFormat),
+    /// Show a duration, or a timestamp since the epoch, as a number of units.
+    Unit(Unit),
+    /// Show a timestamp as a Windows FILETIME: 100 ns intervals since 1601-01-01.
+    FileTime,
+}
+
+impl Conversion {
+    /// Seconds from 1601-01-01, the FILETIME epoch, to the Unix epoch.
+    const FILETIME_EPOCH: i128 = 11_644_473_600;
+
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        if let Some(unit) = Unit::parse(input) {
+            return Some(Self::Unit(unit));
+        }
+        if input.eq_ignore_ascii_case("filetime") {
+            return Some(Self::FileTime);
+        }
+        match Zone::parse(input) {
+            Some(zone) => Some(Self::Zone(zone)),
+            None => DateFormat::parse(input).map(Self::Format),
+        }
+    }
+
+    fn convert(&self, record: &parser::Record) -> Record {
+        let second = Unit::Seconds.nanoseconds();
+        match (self, &record.expression) {
+            (_, Expression::Series(timestamps)) => Record::Series(
+                timestamps
+                    .iter()
+                    .map(|t| {
+                        self.convert(&parser::Record {
+                            expression: Expression::Timestamp(*t),
+                            ..record.clone()
+                        })
+                    })
+                    .collect(),
+            ),
+            (Self::Zone(zone), Expression::Timestamp(t)) => {
+                Record::timestamp(*t, zone.offset_at(*t)).with_format(record.format.clone())
+            }
+            (Self::Zone(zone), Expression::Interval(start, end)) => {
+                Record::interval(*start, *end, zone.offset_at(*start))
+                    .with_format(record.format.clone())
+            }
+            (Self::Format(format), Expression::Timestamp(t)) => {
+                Record::timestamp(*t, record.zone.offset_at(*t)).with_format(format.clone())
+            }
+            (Self::Format(format), Expression::Interval(start, end)) => {
+                Record::interval(*start, *end, record.zone.offset_at(*start))
+                    .with_format(format.clone())
+            }
+            (Self::Unit(unit), Expression::Interval(start, end)) => {
+                Record::Converted(unit.format((*end as i128 - *start as i128) * second))
+            }
+            (Self::Unit(unit), Expression::Timestamp(t)) => {
+                Record::Converted(unit.format(*t as i128 * second))
+            }
+            (Self::Unit(unit), Expression::Duration(d)) => {
+                Record::Converted(unit.format(d.num_milliseconds() as i128 * 1_000_000))
+            }
+            // Like in arithmetic with durations, a number counts seconds.
+            (Self::Unit(unit), Expression::Number(n)) => {
+                Record::Converted(unit.format((n * second as f64).round() as i128))
+            }
+            (Self::FileTime, Expression::Timestamp(t)) => {
+                Record::Converted(((*t as i128 + Self::FILETIME_EPOCH) * 10_000_000).to_string())
+            }
+            (_, Expression::Error(error)) => Record::Error(error.to_string()),
+            (_, expression) => match expression.kind() {
+                Some(kind) => Record::Error(format!("cannot convert {kind} to {self}")),
+                None => Record::None,
+            },
+        }
+    }
+}
+
+impl Display for Conversion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Zone(zone) => write!(f, "{zone}"),
+            Self::Format(format) => write!(f, "{}", format.name()),
+            Self::Unit(unit) => write!(f, "{}", unit.name()),
+            Self::FileTime => write!(f, "filetime"),
+        }
+    }
+}
+
+#[non_exhaustive]
+pub enum Record {
+    DateTime(DateTime<FixedOffset>, DateFormat),
+    /// From a datetime up to, but not including, another, both in the same zone and format.
+    Interval(DateTime<FixedOffset>, DateTime<FixedOffset>, DateFormat),
+    Duration(Duration),
+    Offset(FixedOffset),
+    Format(DateFormat),
+    /// The result of a unit or encoding conversion, shown as is in both columns.
+    Converted(String),
+    Number(f64),
+    Boolean(bool),
+    /// The values of a series, each shown on a row of its own. Never empty.
+    Series(Vec<Record>),
+    /// Why the line has no value, such as a [`parser::EvalError`].
+    Error(String),
+    None,
+}
+
+impl From<&parser::Record> for Record {
+    fn from(record: &parser::Record) -> Self {
+        if let Some(conversion) = &record.conversion {
+            return conversion.convert(record);
+        }
+        match &record.expression {
+            Expression::Timestamp(t) | Expression::Now(t) => {
+                Self::timestamp(*t, record.zone.offset_at(*t)).with_format(record.format.clone())
+            }
+            Expression::Duration(d) => Self::duration(*d),
+            Expression::Interval(start, end) => {
+                Self::interval(*start, *end, record.zone.offset_at(*start))
+                    .with_format(record.format.clone())
+            }
+            Expression::Number(n) => Self::Number(*n),
+            Expression::Boolean(b) => Self::Boolean(*b),
+            Expression::Series(timestamps) => Self::Series(
+                timestamps
+                    .iter()
+                    .map(|t| {
+                        Self::timestamp(*t, record.zone.offset_at(*t))
+                            .with_format(record.format.clone())
+                    })
+                    .collect(),
+            ),
+            Expression::Error(error) => Self::Error(error.to_string()),
+            Expression::Offset(offset) => Self::Offset(*offset),
+            Expression::Format(format) => Self::Format(format.clone()),
+            _ => Self::None,
+        }
+    }
+}
+
+pub trait ToFormattedString {
+    fn to_fmt_string(&self) -> String;
+}
+
+impl ToFormattedString for Duration {
+    fn to_fmt_string(&self) -> String {
+        if *self == Self::seconds(0) {
+            return "0s".to_string();
+        }
+        let (abs, sign) = if self.num_milliseconds() < 0 {
+            (-*self, "-")
+        } else {
+            (*self, "")
+        };
+        let days = abs.num_days();
+        let hours = abs.num_hours() - days * 24;
+        let minutes = abs.num_minutes() - days * 24 * 60 - hours * 60;
+        let seconds = abs.num_seconds() - days * 24 * 60 * 60 - hours * 60 * 60 - minutes * 60;
+        let milliseconds = abs.num_milliseconds()
+            - days * 24 * 60 * 60 * 1000
+            - hours * 60 * 60 * 1000
+            - minutes * 60 * 1000
+            - seconds * 1000;
+        let mut string = sign.to_string();
+        if days > 0 {
+            string = format!("{string}{}d", days);
+        }
+        if hours > 0 {
+            string = format!("{string}{}h", hours);
+        }
+        if minutes > 0 {
+            string = format!("{string}{}m", minutes);
+        }
+        if seconds > 0 {
+            string = format!("{string}{}s", seconds);
+        }
+        if milliseconds > 0 {
+            string = format!("{string}{}ms", milliseconds);
+        }
+        string
+    }
+}
+
+/// The two largest non-zero units of `seconds`, e.g. `3h 12m`.
+fn approximate(seconds: u64) -> String {
+    let units = [(24 * 60 * 60, "d"), (60 * 60, "h"), (60, "m"), (1, "s")];
+    let mut parts = vec![];
+    let mut rest = seconds;
+    for (size, name) in units {
+        if rest >= size {
+            parts.push(format!("{}{name}", rest / size));
+            rest %= size;
+        } else if !parts.is_empty() {
+            // Skip to the end so `1d 0h 5m` becomes `1d`, not `1d 5m`.
+            break;
+        }
+        if parts.len() == 2 {
+            break;
+        }
+    }
+    parts.join(" ")
+}
+
+/// A datetime as its timestamp, nanoseconds and offset in seconds, for [`Record::encode`].
+fn encode_datetime(datetime: &DateTime<FixedOffset>) -> String {
+    format!(
+        "{} {} {}",
+        datetime.timestamp(),
+        datetime.timestamp_subsec_nanos(),
+        datetime.offset().local_minus_utc()
+    )
+}
+
+/// Reads a datetime written by [`encode_datetime`] from the first three `fields`.
+fn decode_datetime(fields: &[&str]) -> Option<DateTime<FixedOffset>> {
+    let number = |i: usize| fields.get(i)?.parse::<i64>().ok();
+    let offset = FixedOffset::east_opt(number(2)?.try_into().ok()?)?;
+    let datetime = DateTime::from_timestamp(number(0)?, number(1)?.try_into().ok()?)?;
+    Some(datetime.with_timezone(&offset))
+}
+
+impl Record {
+    pub fn timestamp(timestamp: i64, offset: FixedOffset) -> Self {
+        match DateTime::<Utc>::from_timestamp(timestamp, 0) {
+            Some(d) => Self::DateTime(d.with_timezone(&offset), DateFormat::Default),
+            None => Self::None,
+        }
+    }
+
+    pub fn interval(start: i64, end: i64, offset: FixedOffset) -> Self {
+        match (Self::timestamp(start, offset), Self::timestamp(end, offset)) {
+            (Self::DateTime(start, _), Self::DateTime(end, _)) => {
+                Self::Interval(start, end, DateFormat::Default)
+            }
+            _ => Self::None,
+        }
+    }
+
+    /// Sets how a datetime is written; other records are unchanged.
+    pub fn with_format(self, format: DateFormat) -> Self {
+        match self {
+            Self::DateTime(datetime, _) => Self::DateTime(datetime, format),
+            Self::Interval(start, end, _) => Self::Interval(start, end, format),
+            record => record,
+        }
+    }
+
+    pub const fn duration(duration: Duration) -> Self {
+        Self::Duration(duration)
+    }
+
+    pub const fn empty() -> Self {
+        Self::None
+    }
+
+    /// The rows this record is shown on: one for every value of a series, and just itself
+    /// otherwise.
+    pub fn rows(&self) -> &[Self] {
+        match self {
+            Self::Series(rows) => rows,
+            record => std::slice::from_ref(record),
+        }
+    }
+
+    /// A compact, lossless text form of the record, for passing it between threads.
+    pub fn encode(&self) -> String {
+        match self {
+            Self::Series(rows) => {
+                let rows: Vec<_> = rows.iter().map(Self::encode).collect();
+                format!("s\n{}", rows.join("\n"))
+            }
+            Self::DateTime(datetime, format) => {
+                format!("t {} {}", encode_datetime(datetime), format.name())
+            }
+            Self::Duration(duration) => {
+                format!("d {} {}", duration.num_seconds(), duration.subsec_nanos())
+            }
+            Self::Offset(offset) => format!("o {}", offset.local_minus_utc()),
+            Self::Format(format) => format!("f {}", format.name()),
+            Self::Converted(text) => format!("c {text}"),
+            Self::Interval(start, end, format) => format!(
+                "i {} {} {}",
+                encode_datetime(start),
+                encode_datetime(end),
+                format.name()
+            ),
+            Self::Number(n) => format!("u {n}"),
+            Self::Boolean(b) => format!("b {b}"),
+            Self::Error(error) => format!("e {error}"),
+            Self::None => "n".to_string(),
+        }
+    }
+
+    /// Reads a record written by [`Record::encode`].
+    pub fn decode(text: &str) -> Option<Self> {
+        if let Some(rows) = text.strip_prefix("s\n") {
+            return rows
+                .split('\n')
+                .map(Self::decode)
+                .collect::<Option<_>>()
+                .map(Self::Series);
+        }
+        let (kind, rest) = text.split_once(' ').unwrap_or((text, ""));
+        let fields: Vec<_> = rest.splitn(4, ' ').collect();
+        let number = |i: usize| fields.get(i)?.parse::<i64>().ok();
+        let record = match kind {
+            "t" => Self::DateTime(
+                decode_datetime(&fields)?,
+                DateFormat::parse(fields.get(3)?)?,
+            ),
+            "d" => {
+                Self::Duration(Duration::seconds(number(0)?) + Duration::nanoseconds(number(1)?))
+            }
+            "o" => Self::Offset(FixedOffset::east_opt(number(0)?.try_into().ok()?)?),
+            "f" => Self::Format(DateFormat::parse(rest)?),
+            "c" => Self::Converted(rest.to_string()),
+            "i" => {
+                let fields: Vec<_> = rest.splitn(7, ' ').collect();
+                let start = decode_datetime(&fields)?;
+                let end = decode_datetime(fields.get(3..)?)?;
+                Self::Interval(start, end, DateFormat::parse(fields.get(6)?)?)
+            }
+            "u" => Self::Number(rest.parse().ok()?),
+            "b" => Self::Boolean(rest.parse().ok()?),
+            "e" => Self::Error(rest.to_string()),
+            "n" => Self::None,
+            _ => return None,
+        };
+        Some(record)
+    }
+
+    pub fn to_datetime_string(&self) -> String {
+        match self {
+            Self::DateTime(datetime, format) => format.format(datetime),
+            Self::Interval(start, end, format) => {
+                format!("{} .. {}", format.format(start), format.format(end))
+            }
+            Self::Duration(duration) => duration.to_fmt_string(),
+            Self::Offset(offset) => format!("UTC{}", offset),
+            Self::Format(format) => format!("FORMAT {}", format.name()),
+            Self::Converted(value) => value.clone(),
+            Self::Number(n) => n.to_string(),
+            Self::Boolean(b) => b.to_string(),
+            Self::Series(rows) => summary(rows, Self::to_datetime_string),
+            Self::Error(error) => error.clone(),
+            _ => "...".to_string(),
+        }
+    }
+
+    /// How far a datetime is from `now`, e.g. `3h 12m ago` or `in 2d 4h`, or for an
+    /// interval how far its start is, unless it is `ongoing`. Other records have no
+    /// relative value and give an empty string.
+    pub fn to_relative_string(&self, now: i64) -> String {
+        let datetime = match self {
+            Self::DateTime(datetime, _) => datetime,
+            Self::Interval(start, end, _) if start.timestamp() <= now && now < end.timestamp() => {
+                return "ongoing".to_string();
+            }
+            Self::Interval(start, ..) => start,
+            _ => return "".to_string(),
+        };
+        let seconds = datetime.timestamp().saturating_sub(now);
+        match seconds {
+            0 => "now".to_string(),
+            ..0 => format!("{} ago", approximate(seconds.unsigned_abs())),
+            _ => format!("in {}", approximate(seconds.unsigned_abs())),
+        }
+    }
+
+    pub fn to_timestamp_string(&self) -> String {
+        match self {
+            Self::DateTime(datetime, _) => datetime.timestamp().to_string(),
+            Self::Interval(start, end, _) => format!("{}..{}", start.timestamp(), end.timestamp()),
+            Self::Duration(duration) => (duration.num_milliseconds() as f64 / 1000.).to_string(),
+            Self::Offset(offset) => format!("UTC{}", offset),
+            Self::Format(format) => format!("FORMAT {}", format.name()),
+            Self::Converted(value) => value.clone(),
+            Self::Number(n) => n.to_string(),
+            Self::Boolean(b) => b.to_string(),
+            Self::Series(rows) => summary(rows, Self::to_timestamp_string),
+            _ => "...".to_string(),
+        }
+    }
+}
+
+/// The first row of a series and how many follow, e.g. `1704067200 (+11 more)`, for
+/// where a series has to fit on one row.
+fn summary(rows: &[Record], column: fn(&Record) -> String) -> String {
+    match rows {
+        [first] => column(first),
+        [first, rest @ ..] => format!("{} (+{} more)", column(first), rest.len()),
+        [] => "...".to_string(),
+    }
+}
+
+/// Parses every line of `input` and converts the results into displayable records.
+pub fn parse(input: &str, now: i64) -> Vec<Record> {
+    parse_with(input, now, Settings::default())
+}
+
+/// Like [`parse`], but starting from `settings` instead of UTC and the default format.
+pub fn parse_with(input: &str, now: i64, settings: Settings) -> Vec<Record> {
+    let records = parser::parse_with(input, now, settings);
+    records.iter().map(std::convert::Into::into).collect()
+}
+
+/// The input text together with one evaluated record per line.
+///
+/// Its `Display` implementation is the plain-text table copied to the clipboard:
+/// every input line padded to the longest one, followed by its datetime column.
+pub struct Worksheet {
+    pub input: String,
+    pub records: Vec<Record>,
+    /// Lines that could not be evaluated because their references go round in a circle.
+    pub circular: Vec<usize>,
+    /// The lines of `circular` in the circle themselves, rather than depending on it.
+    pub cycles: Vec<usize>,
+    /// Whether any line may use the current time, so the worksheet changes as it ticks.
+    pub uses_now: bool,
+    /// The last evaluation, so [`Worksheet::update`] only evaluates what changed.
+    cache: parser::Cache,
+}
+
+impl Worksheet {
+    pub fn new(input: String, now: i64) -> Self {
+        Self::with_settings(input, now, Settings::default())
+    }
+
+    pub fn with_settings(input: String, now: i64, settings: Settings) -> Self {
+        let mut sheet = Self::empty();
+        sheet.update(input, now, settings);
+        sheet
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            input: "".to_string(),
+            records: vec![Record::empty()],
+            circular: vec![],
+            cycles: vec![],
+            uses_now: false,
+            cache: parser::Cache::default(),
+        }
+    }
+}
+
+impl Worksheet {
+    /// Replaces the input and evaluates it again, reusing the values of the lines that
+    /// did not change, unless their references now find different values.
+    pub fn update(&mut self, input: String, now: i64, settings: Settings) {
+        let evaluation = parser::evaluate_cached(&input, now, settings, &mut self.cache);
+        self.records = evaluation.records.iter().map(Into::into).collect();
+        self.circular = evaluation.circular;
+        self.cycles = evaluation.cycles;
+        self.uses_now = self.cache.uses_now();
+        self.input = input;
+    }
+}
+
+impl Worksheet {
+    /// The plain-text table with an extra column telling how far each datetime is from `now`.
+    pub fn to_relative_string(&self, now: i64) -> String {
+        self.text(Some(now))
+    }
+
+    /// Every row of the worksheet with the index and text of its input line. The text is
+    /// empty for the rows of a series after the first.
+    pub(crate) fn expanded(&self) -> impl Iterator<Item = (usize, &str, &Record)> {
+        self.input
+            .split('\n')
+            .zip(&self.records)
+            .enumerate()
+            .flat_map(|(line, (input, record))| {
+                let inputs = std::iter::once(input.trim()).chain(std::iter::repeat(""));
+                inputs
+                    .zip(record.rows())
+                    .map(move |(input, row)| (line, input, row))
+            })
+    }
+
+    fn text(&self, relative_to: Option<i64>) -> String {
+        let max_length = self.input.split('\n').map(|s| s.trim().len()).max();
+        let max_length = max_length.unwrap_or(0);
+        let datetimes: Vec<_> = self
+            .expanded()
+            .map(|(_, _, record)| record.to_datetime_string())
+            .collect();
+        let max_datetime = datetimes.iter().map(|s| s.len()).max().unwrap_or(0);
+        self.expanded()
+            .zip(&datetimes)
+            .map(|((_, input, record), datetime)| match relative_to {
+                Some(now) => {
+                    let relative = record.to_relative_string(now);
+                    let line = format!("{input:max_length$} {datetime:max_datetime$} {relative}");
+                    line.trim_end().to_string()
+                }
+                None => format!("{input:max_length$} {datetime}"),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Display for Worksheet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text(None))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::*;
+    #[test]
+    fn test() {
+        let input: String = "#UTC+1\n12323123\n'1970-05-23 16:05:23'".to_string();
+        let sheet = Worksheet::new(input, 1);
+        assert_eq!(sheet.records.len(), 3);
+        assert_eq!(
+            sheet.to_string(),
+            concat!(
+                "#UTC+1                UTC+01:00\n",
+                "12323123              1970-05-23 16:05:23 +01:00\n",
+                "'1970-05-23 16:05:23' 1970-05-23 16:05:23 +01:00"
+            ),
+        );
+
+        assert!(!sheet.uses_now);
+        assert!(Worksheet::new("#1 + 1h\nnow - 1d".to_string(), 1).uses_now);
+
+        // A named zone is shown with its offset at each point in time.
+        let mut sheet = Worksheet::empty();
+        let settings = Settings {
+            zone: Zone::parse("Europe/Berlin").unwrap(),
+            ..Settings::default()
+        };
+        sheet.update("'2024-01-15'\n'2024-07-15'".to_string(), 0, settings);
+        assert_eq!(
+            sheet.to_string(),
+            concat!(
+                "'2024-01-15' 2024-01-15 00:00:00 +01:00\n",
+                "'2024-07-15' 2024-07-15 00:00:00 +02:00"
+            ),
+        );
+    }
+
+    #[test]
+    fn formats() {
+        let input = "#FORMAT iso\n0\n#FORMAT %d/%m/%Y %H:%M\n#UTC+1\n0\n1h".to_string();
+        assert_eq!(
+            Worksheet::new(input, 0).to_string(),
+            concat!(
+                "#FORMAT iso            FORMAT iso\n",
+                "0                      1970-01-01T00:00:00+00:00\n",
+                "#FORMAT %d/%m/%Y %H:%M FORMAT %d/%m/%Y %H:%M\n",
+                "#UTC+1                 UTC+01:00\n",
+                "0                      01/01/1970 01:00\n",
+                "1h                     1h",
+            ),
+        );
+
+        let datetime = FixedOffset::east_opt(5 * 3600)
+            .unwrap()
+            .with_ymd_and_hms(2024, 1, 15, 12, 0, 0)
+            .unwrap();
+        let formatted: Vec<_> = DateFormat::PRESETS
+            .iter()
+            .map(|format| format.format(&datetime))
+            .collect();
+        assert_eq!(
+            formatted,
+            vec![
+                "2024-01-15 12:00:00 +05:00",
+                "2024-01-15T12:00:00+05:00",
+                "Mon, 15 Jan 2024 12:00:00 +0500",
+                "2024-01-15T12:00:00.000+05:00",
+                "Mon, 15 Jan 2024 07:00:00 GMT",
+                "2024-01-15",
+                "12:00:00",
+                "Monday",
+            ]
+        );
+        assert_eq!(DateFormat::parse("RFC2822"), Some(DateFormat::Rfc2822));
+        assert_eq!(DateFormat::parse("isoo"), None);
+        assert_eq!(DateFormat::parse("%Y %Q"), None);
+        assert_eq!(DateFormat::parse("%Y %#z"), None);
+    }
+
+    #[test]
+    fn conversions() {
+        let input = [
+            "1748000000 in Asia/Tokyo",
+            "#1 as rfc2822",
+            "#1 in UTC-5",
+            "#1 in ms",
+            "#1 as filetime",
+            "#1 - 1748000000 + 90s in minutes",
+            "2h in ms",
+            "2h in weeks",
+            "1d as iso",
+            "1 + 2 in minutes",
+            "1 + 2 as filetime",
+            "1h in Asia/Tokyo",
+        ];
+        let records = parse(&input.join("\n"), 0);
+        let columns: Vec<_> = records
+            .iter()
+            .map(|r| (r.to_datetime_string(), r.to_timestamp_string()))
+            .collect();
+        let pair = |a: &str, b: &str| (a.to_string(), b.to_string());
+        let same = |a: &str| pair(a, a);
+        assert_eq!(
+            columns,
+            vec![
+                pair("2025-05-23 20:33:20 +09:00", "1748000000"),
+                pair("Fri, 23 May 2025 11:33:20 +0000", "1748000000"),
+                pair("2025-05-23 06:33:20 -05:00", "1748000000"),
+                same("1748000000000"),
+                same("133924736000000000"),
+                same("1.5"),
+                same("7200000"),
+                same("0.011904761904761904"),
+                pair("cannot convert a duration to iso", "..."),
+                same("0.05"),
+                pair("cannot convert a number to filetime", "..."),
+                pair("cannot convert a duration to Asia/Tokyo", "..."),
+            ]
+        );
+    }
+
+    #[test]
+    fn relative() {
+        let now = 1_000_000;
+        let relative = |t: i64| {
+            Record::timestamp(now + t, FixedOffset::east_opt(0).unwrap()).to_relative_string(now)
+        };
+        assert_eq!(relative(0), "now");
+        assert_eq!(relative(-59), "59s ago");
+        assert_eq!(relative(-(3 * 3600 + 12 * 60 + 5)), "3h 12m ago");
+        assert_eq!(relative(2 * 86400 + 4 * 3600 + 59), "in 2d 4h");
+        assert_eq!(relative(86400 + 5 * 60), "in 1d");
+        assert_eq!(
+            Record::duration(Duration::hours(1)).to_relative_string(now),
+            ""
+        );
+
+        let sheet = Worksheet::new("now - 90m\n1h\nnow + 2d".to_string(), now);
+        assert_eq!(
+            sheet.to_relative_string(now),
+            concat!(
+                "now - 90m 1970-01-12 12:16:40 +00:00 1h 30m ago\n",
+                "1h        1h\n",
+                "now + 2d  1970-01-14 13:46:40 +00:00 in 2d",
+            )
+        );
+    }
+
+    #[test]
+    fn encoding() {
+        let sheet = Worksheet::new(
+            "#UTC-3\n#FORMAT %d/%m %H:%M\n1748000000\n1s - 2.5s\n2h in minutes\n1 + 0.5\nnow + now\nnope\n#3..+1h"
+                .to_string(),
+            0,
+        );
+        let encoded: Vec<_> = sheet.records.iter().map(Record::encode).collect();
+        assert_eq!(
+            encoded,
+            vec![
+                "o -10800",
+                "f %d/%m %H:%M",
+                "t 1748000000 0 -10800 %d/%m %H:%M",
+                "d -1 -500000000",
+                "c 120",
+                "u 1.5",
+                "e cannot add a timestamp to a timestamp",
+                "n",
+                "i 1748000000 0 -10800 1748003600 0 -10800 %d/%m %H:%M",
+            ]
+        );
+        for (record, text) in sheet.records.iter().zip(&encoded) {
+            let decoded = Record::decode(text).unwrap();
+            assert_eq!(decoded.encode(), *text);
+            assert_eq!(decoded.to_datetime_string(), record.to_datetime_string());
+        }
+        assert!(Record::decode("t 1 x").is_none());
+        // Both ends keep their own offset and fraction of a second.
+        let start = DateTime::from_timestamp(0, 500).unwrap();
+        let end = DateTime::from_timestamp(3600, 0).unwrap();
+        let interval = Record::Interval(
+            start.with_timezone(&FixedOffset::east_opt(3600).unwrap()),
+            end.with_timezone(&FixedOffset::east_opt(7200).unwrap()),
+            DateFormat::Iso,
+        );
+        assert_eq!(interval.encode(), "i 0 500 3600 3600 0 7200 iso");
+        assert_eq!(
+            Record::decode(&interval.encode()).map(|record| record.encode()),
+            Some(interval.encode())
+        );
+    }
+
+    #[test]
+    fn intervals() {
+        let input = "#UTC+1\n'2024-01-01' .. +3d\n#2 in Asia/Tokyo\n#2 in hours\n#2 contains now";
+        let records = parse(input, 1704100000);
+        let columns: Vec<_> = records
+            .iter()
+            .skip(1)
+            .map(|r| (r.to_datetime_string(), r.to_timestamp_string()))
+            .collect();
+        let pair = |a: &str, b: &str| (a.to_string(), b.to_string());
+        assert_eq!(
+            columns,
+            vec![
+                pair(
+                    "2024-01-01 00:00:00 +01:00 .. 2024-01-04 00:00:00 +01:00",
+                    "1704063600..1704322800"
+                ),
+                pair(
+                    "2024-01-01 08:00:00 +09:00 .. 2024-01-04 08:00:00 +09:00",
+                    "1704063600..1704322800"
+                ),
+                pair("72", "72"),
+                pair("true", "true"),
+            ]
+        );
+        assert_eq!(records[1].to_relative_string(1704100000), "ongoing");
+        assert_eq!(records[1].to_relative_string(1704000000), "in 17h 40m");
+        for record in &records {
+            let encoded = record.encode();
+            assert_eq!(Record::decode(&encoded).unwrap().encode(), encoded);
+        }
+    }
+
+    #[test]
+    fn series() {
+        let sheet = Worksheet::new("1h\nseries(0, 1d, 3) as date\n#2".to_string(), 0);
+        assert_eq!(
+            sheet.to_string(),
+            concat!(
+                "1h                       1h\n",
+                "series(0, 1d, 3) as date 1970-01-01\n",
+                "                         1970-01-02\n",
+                "                         1970-01-03\n",
+                "#2                       1970-01-01 00:00:00 +00:00\n",
+                "                         1970-01-02 00:00:00 +00:00\n",
+                "                         1970-01-03 00:00:00 +00:00",
+            )
+        );
+        // Where a series has to fit on one row, its first row stands for it.
+        assert_eq!(sheet.records[2].to_timestamp_string(), "0 (+2 more)");
+        assert_eq!(sheet.records[2].rows().len(), 3);
+        let encoded = sheet.records[1].encode();
+        assert_eq!(Record::decode(&encoded).unwrap().encode(), encoded);
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(Duration::seconds(0).to_fmt_string(), "0s");
+        assert_eq!(
+            (Duration::hours(26) + Duration::milliseconds(1500)).to_fmt_string(),
+            "1d2h1s500ms"
+        );
+        assert_eq!(Duration::minutes(-90).to_fmt_string(), "-1h30m");
+    }
+}
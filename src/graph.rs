@@ -0,0 +1,319 @@
+//! The order in which the lines of a worksheet are evaluated.
+//!
+//! A line can refer to any other line, above or below it, so lines are evaluated in
+//! dependency order rather than top to bottom: a summary at the top of a worksheet can
+//! refer to the data below it. Lines that refer to themselves, directly or through other
+//! lines, cannot be evaluated and are reported as circular, and so are the lines that
+//! depend on them.
+//!
+//! Besides its `#N` references, a line depends on the closest header above it, whose
+//! zone, format and `now` it uses. `_` and `#-N` depend on the line they point at, while
+//! `#prev`, `#first` and `#last-timestamp` depend on every line above, since which one they
+//! find depends on what those lines evaluate to.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::parser::{Token, highlight};
+
+/// The lines of a worksheet and what each of them depends on.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Graph {
+    /// For every line, the other lines its references point at.
+    references: Vec<Vec<usize>>,
+    /// For every line, whether it depends on every line above it.
+    above: Vec<bool>,
+    /// For every line, the closest header above it.
+    headers: Vec<Option<usize>>,
+}
+
+/// The evaluation order found by [`Graph::order`].
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Order {
+    /// Lines that can be evaluated, each after the lines it depends on.
+    pub lines: Vec<usize>,
+    /// Lines in a cycle of references, or depending on one, in input order.
+    pub circular: Vec<usize>,
+    /// The lines of `circular` in a cycle themselves, in input order.
+    pub cycles: Vec<usize>,
+}
+
+/// What a single line depends on, which only takes its text to work out.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Dependencies {
+    pub(crate) references: Vec<Reference>,
+    /// Whether the line is a header, which the lines below depend on.
+    pub(crate) header: bool,
+    /// Whether the line may use the current time, and so change as the clock ticks.
+    pub(crate) now: bool,
+}
+
+impl Dependencies {
+    pub fn new(line: &str) -> Self {
+        let tokens = highlight(line);
+        let references = tokens
+            .iter()
+            .filter(|(_, token)| *token == Token::Reference)
+            .map(|(range, _)| Reference::parse(&line[range.clone()]))
+            .collect();
+        Self {
+            references,
+            header: matches!(tokens.first(), Some((_, Token::Header))),
+            now: line.contains("now"),
+        }
+    }
+}
+
+impl Graph {
+    pub fn new(input: &str) -> Self {
+        let lines: Vec<_> = input.split('\n').map(Dependencies::new).collect();
+        Self::with_dependencies(&lines)
+    }
+
+    /// The graph of lines whose dependencies are already known.
+    pub fn with_dependencies(lines: &[Dependencies]) -> Self {
+        let mut references = Vec::with_capacity(lines.len());
+        let mut above = Vec::with_capacity(lines.len());
+        let mut headers = Vec::with_capacity(lines.len());
+        let mut header = None;
+        for (index, line) in lines.iter().enumerate() {
+            let mut targets = vec![];
+            let mut everything_above = false;
+            for reference in &line.references {
+                match reference {
+                    Reference::Line(target) => targets.push(*target),
+                    Reference::Above(n) => targets.extend(index.checked_sub(*n)),
+                    _ => everything_above = true,
+                }
+            }
+            targets.sort_unstable();
+            targets.dedup();
+            targets.retain(|target| *target < lines.len());
+            references.push(targets);
+            above.push(everything_above);
+            headers.push(header);
+            if line.header {
+                header = Some(index);
+            }
+        }
+        Self {
+            references,
+            above,
+            headers,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.references.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.references.is_empty()
+    }
+
+    /// The closest header above `line`, whose settings it is evaluated with.
+    pub fn header(&self, line: usize) -> Option<usize> {
+        self.headers.get(line).copied().flatten()
+    }
+
+    /// Sorts the lines so that every line comes after the ones it depends on. Lines that
+    /// do not depend on each other keep their order in the input.
+    pub fn order(&self) -> Order {
+        // Node `2 * i + 1` is line `i`. Node `2 * i` stands for all the lines above line `i`,
+        // so depending on every line above costs one edge instead of one per line.
+        let nodes = 2 * self.len() + 1;
+        let mut dependents = vec![vec![]; nodes];
+        let mut pending = vec![0; nodes];
+        let mut depend = |node: usize, on: usize| {
+            dependents[on].push(node);
+            pending[node] += 1;
+        };
+        for line in 0..self.len() {
+            let node = 2 * line + 1;
+            depend(2 * line + 2, 2 * line);
+            depend(2 * line + 2, node);
+            for target in &self.references[line] {
+                depend(node, 2 * target + 1);
+            }
+            if let Some(header) = self.headers[line] {
+                depend(node, 2 * header + 1);
+            }
+            if self.above[line] {
+                depend(node, 2 * line);
+            }
+        }
+
+        let mut ready: BinaryHeap<_> = (0..nodes)
+            .filter(|node| pending[*node] == 0)
+            .map(Reverse)
+            .collect();
+        let mut evaluated = vec![false; nodes];
+        let mut order = Order::default();
+        while let Some(Reverse(node)) = ready.pop() {
+            evaluated[node] = true;
+            if node % 2 == 1 {
+                order.lines.push(node / 2);
+            }
+            for dependent in &dependents[node] {
+                pending[*dependent] -= 1;
+                if pending[*dependent] == 0 {
+                    ready.push(Reverse(*dependent));
+                }
+            }
+        }
+        order.circular = (0..self.len())
+            .filter(|line| !evaluated[2 * line + 1])
+            .collect();
+        if !order.circular.is_empty() {
+            let component = components(&dependents, &evaluated);
+            let mut sizes = vec![0; nodes];
+            for node in (0..nodes).filter(|node| !evaluated[*node]) {
+                sizes[component[node]] += 1;
+            }
+            order.cycles = order
+                .circular
+                .iter()
+                .copied()
+                .filter(|line| {
+                    let node = 2 * line + 1;
+                    sizes[component[node]] > 1 || dependents[node].contains(&node)
+                })
+                .collect();
+        }
+        order
+    }
+}
+
+/// The strongly connected component of every node not in `skip`, numbered from 0: the
+/// nodes of a component all reach each other through `edges`, and no other node does.
+fn components(edges: &[Vec<usize>], skip: &[bool]) -> Vec<usize> {
+    let nodes = edges.len();
+    let mut reversed = vec![vec![]; nodes];
+    for (node, targets) in edges.iter().enumerate() {
+        for target in targets {
+            reversed[*target].push(node);
+        }
+    }
+    // Kosaraju's algorithm: a depth-first search along the edges, then one against them
+    // from the nodes finished last, which only reaches the nodes of their component.
+    let mut finished = Vec::with_capacity(nodes);
+    let mut visited = skip.to_vec();
+    for root in 0..nodes {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        let mut stack = vec![(root, 0)];
+        while let Some(&(node, next)) = stack.last() {
+            match edges[node].get(next) {
+                Some(&target) => {
+                    let top = stack.len() - 1;
+                    stack[top].1 += 1;
+                    if !visited[target] {
+                        visited[target] = true;
+                        stack.push((target, 0));
+                    }
+                }
+                None => {
+                    finished.push(node);
+                    stack.pop();
+                }
+            }
+        }
+    }
+    let mut component = vec![usize::MAX; nodes];
+    let mut count = 0;
+    for &root in finished.iter().rev() {
+        if component[root] != usize::MAX {
+            continue;
+        }
+        component[root] = count;
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            for &source in &reversed[node] {
+                if !skip[source] && component[source] == usize::MAX {
+                    component[source] = count;
+                    stack.push(source);
+                }
+            }
+        }
+        count += 1;
+    }
+    component
+}
+
+/// What a reference token points at, as seen before evaluating anything.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Reference {
+    /// `#N` or `$N`, as a line index.
+    Line(usize),
+    /// `_` or `#-N`: the line `N` above.
+    Above(usize),
+    /// `#prev`, `#first` and `#last-timestamp`, which look through the lines above.
+    Previous,
+    First,
+    LastTimestamp,
+}
+
+impl Reference {
+    fn parse(reference: &str) -> Self {
+        if reference == "_" {
+            return Self::Above(1);
+        }
+        let reference = reference.trim_start_matches(['#', '$']);
+        if let Some(n) = reference.strip_prefix('-') {
+            return Self::Above(n.parse().unwrap_or(usize::MAX));
+        }
+        match reference {
+            "first" => Self::First,
+            "last-timestamp" => Self::LastTimestamp,
+            "prev" => Self::Previous,
+            // `#0` never pointed at a line.
+            n => Self::Line(
+                n.parse::<usize>()
+                    .ok()
+                    .and_then(|n| n.checked_sub(1))
+                    .unwrap_or(usize::MAX),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn order() {
+        let graph = Graph::new("#3 - #2\n1h\n100\n_ + #-2");
+        assert_eq!(graph.order().lines, vec![1, 2, 0, 3]);
+        assert!(graph.order().circular.is_empty());
+
+        let graph = Graph::new("#UTC+1\n#4\n#NOW 0\nnow\n#prev");
+        assert_eq!(graph.header(1), Some(0));
+        assert_eq!(graph.header(4), Some(2));
+        assert_eq!(graph.order().lines, vec![0, 2, 3, 1, 4]);
+    }
+
+    #[test]
+    fn cycles() {
+        let graph = Graph::new("#2\n#1 + 1h\n100\n#3\n#5");
+        let order = graph.order();
+        assert_eq!(order.lines, vec![2, 3]);
+        assert_eq!(order.circular, vec![0, 1, 4]);
+        assert_eq!(order.cycles, vec![0, 1, 4]);
+
+        // `#prev` depends on every line above, including one that refers back to it.
+        let order = Graph::new("#3\n1h\n#prev").order();
+        assert_eq!(order.circular, vec![0, 2]);
+        assert_eq!(order.cycles, vec![0, 2]);
+
+        // Lines only depending on a cycle, or between two, are not in one.
+        let order = Graph::new("#2\n#1\n#1 + 1h\n#5 - #3\n#4").order();
+        assert_eq!(order.circular, vec![0, 1, 2, 3, 4]);
+        assert_eq!(order.cycles, vec![0, 1, 3, 4]);
+        let order = Graph::new("#2\n#1\n#UTC+1\n#2 + 1h\n_").order();
+        assert_eq!(order.circular, vec![0, 1, 3, 4]);
+        assert_eq!(order.cycles, vec![0, 1]);
+    }
+}
@@ -0,0 +1,29 @@
+//! Unix time calculator.
+//!
+//! The grammar and evaluation live in [`parser`], which evaluates lines in the order
+//! worked out by [`graph`] so references can point below them, while [`format`] turns the
+//! evaluated expressions into the strings shown in the datetime and timestamp
+//! columns, and [`export`] writes whole worksheets as CSV, TSV, JSON or Markdown.
+//! [`complete`] suggests how to finish the word being typed, and [`renumber`] keeps `#N`
+//! references on their lines while lines are inserted or deleted.
+//! Nothing in this library depends on the browser, so it can be used
+//! from native code as well as from the Yew front-end. With the `permalink` feature,
+//! [`permalink`] encodes worksheets into URL fragments for sharing. With the `web` feature,
+//! [`worker`] evaluates large worksheets in a web worker.
+
+pub mod complete;
+pub mod export;
+pub mod format;
+pub mod graph;
+pub mod parser;
+#[cfg(feature = "permalink")]
+pub mod permalink;
+pub mod renumber;
+#[cfg(feature = "web")]
+pub mod worker;
+
+pub use export::{Export, Row};
+pub use format::{
+    Conversion, DateFormat, Record, ToFormattedString, Unit, Worksheet, parse, parse_with,
+};
+pub use parser::{Settings, Zone};
@@ -1,132 +1,333 @@
 use std::fmt::{self, Display};
+use std::ops::Range;
 
-use chrono::{DateTime, Duration, FixedOffset, Utc};
-use parser::Expression;
+use gloo_events::EventListener;
+use gloo_timers::callback::{Interval, Timeout};
+use gloo_worker::{Spawnable, WorkerBridge};
 use thiserror::Error;
-use web_sys::HtmlInputElement;
+use unix_time_calculator::complete::{self, Completion};
+use unix_time_calculator::parser::{ParseError, check, highlight};
+use unix_time_calculator::permalink::Permalink;
+use unix_time_calculator::renumber::renumber;
+use unix_time_calculator::worker::{Chunk, Evaluate, Evaluator};
+use unix_time_calculator::{Export, Record, Settings, Worksheet, Zone};
 use wasm_bindgen::prelude::*;
-use yew::{html, Component, Context, Html, InputEvent, TargetCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Element, HtmlInputElement, HtmlTextAreaElement};
+use yew::{
+    Component, Context, Event, Html, InputEvent, KeyboardEvent, MouseEvent, NodeRef, TargetCast,
+    classes, html,
+};
 
 #[wasm_bindgen(inline_js = "
 export function write_to_clipboard(text) {
-    if (navigator.clipboard) { navigator.clipboard.writeText(text).catch(() => {}); return true; }
-    return false;
+    return navigator.clipboard ? navigator.clipboard.writeText(text) : null;
+}
+export function download(filename, text, type) {
+    const url = URL.createObjectURL(new Blob([text], { type }));
+    const link = document.createElement('a');
+    link.href = url;
+    link.download = filename;
+    link.click();
+    URL.revokeObjectURL(url);
+}
+export function browser_time_zone() {
+    try { return Intl.DateTimeFormat().resolvedOptions().timeZone || ''; } catch (e) { return ''; }
 }
 ")]
 extern "C" {
-    fn write_to_clipboard(text: &str) -> bool;
+    /// Resolves once the text is on the clipboard, or is `None` without a clipboard API.
+    fn write_to_clipboard(text: &str) -> Option<js_sys::Promise>;
+    fn download(filename: &str, text: &str, mime_type: &str);
+    fn browser_time_zone() -> String;
 }
-mod parser;
+
+/// How many completions are listed below the line being edited.
+const MAX_SUGGESTIONS: usize = 8;
+
+/// Worksheets with at least this many lines are evaluated in the web worker.
+const WORKER_LINES: usize = 2000;
+/// The script trunk builds for the `evaluator` binary.
+const EVALUATOR_LOADER: &str = "evaluator_loader.js";
+
+/// Rows rendered above and below the visible ones, so scrolling does not show gaps.
+const OVERSCAN_ROWS: usize = 30;
+/// Rows rendered before the page has been laid out and the visible ones are known.
+const INITIAL_ROWS: usize = 100;
+/// The height of a line, in pixels, assumed until the editor has been laid out: the
+/// `--line-height` of `index.scss`.
+const INITIAL_LINE_HEIGHT: f64 = 18.5714;
+
+/// How long the confirmation of a copy stays on screen.
+const TOAST_MILLIS: u32 = 2000;
+
+/// The `localStorage` key the zone picked by the user is remembered under.
+const ZONE_KEY: &str = "utc.zone";
+/// The `localStorage` keys of the tab names, one per line, and of the selected tab.
+/// The input of tab `i` is kept under `utc.tabs.{i}`.
+const TABS_KEY: &str = "utc.tabs";
+const ACTIVE_TAB_KEY: &str = "utc.tabs.active";
 
 fn now() -> i64 {
     (js_sys::Date::now() / 1000.0) as i64
 }
 
-fn parse(input: &str, now: i64) -> Vec<Record> {
-    let records = parser::parse(input, now);
-    records.iter().map(std::convert::Into::into).collect()
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
 }
 
-enum Msg {
-    InputValue(String),
-    CopyToClipboard,
+/// The browser's own zone, by IANA name when the browser tells it, or else as the
+/// current offset from UTC.
+fn browser_zone() -> Zone {
+    Zone::parse(&browser_time_zone()).unwrap_or_else(|| {
+        let minutes = -js_sys::Date::new_0().get_timezone_offset() as i32;
+        Zone::parse(&format!("{:+03}:{:02}", minutes / 60, (minutes % 60).abs()))
+            .unwrap_or(Zone::Fixed(chrono::FixedOffset::east_opt(0).unwrap()))
+    })
 }
 
-#[non_exhaustive]
-enum Record {
-    DateTime(DateTime<FixedOffset>),
-    Duration(Duration),
-    Offset(FixedOffset),
-    None,
+/// A named worksheet, kept in `localStorage` so it survives reloads.
+#[derive(Clone)]
+struct Tab {
+    name: String,
+    input: String,
 }
 
-impl From<&parser::Record> for Record {
-    fn from(record: &parser::Record) -> Self {
-        match record.expression {
-            Expression::Timestamp(t) => Self::timestamp(t, record.offset),
-            Expression::Duration(d) => Self::duration(d),
-            Expression::Offset(offset) => Self::Offset(offset),
-            _ => Self::None,
+impl Tab {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            input: String::new(),
         }
     }
 }
 
-pub trait ToFormattedString {
-    fn to_fmt_string(&self) -> String;
+fn tab_key(index: usize) -> String {
+    format!("{TABS_KEY}.{index}")
 }
 
-impl ToFormattedString for Duration {
-    fn to_fmt_string(&self) -> String {
-        if *self == Self::seconds(0) {
-            return "0s".to_string();
-        }
-        let (abs, sign) = if self.num_milliseconds() < 0 {
-            (-*self, "-")
-        } else {
-            (*self, "")
-        };
-        let days = abs.num_days();
-        let hours = abs.num_hours() - days * 24;
-        let minutes = abs.num_minutes() - days * 24 * 60 - hours * 60;
-        let seconds = abs.num_seconds() - days * 24 * 60 * 60 - hours * 60 * 60 - minutes * 60;
-        let milliseconds = abs.num_milliseconds()
-            - days * 24 * 60 * 60 * 1000
-            - hours * 60 * 60 * 1000
-            - minutes * 60 * 1000
-            - seconds * 1000;
-        let mut string = sign.to_string();
-        if days > 0 {
-            string = format!("{string}{}d", days);
-        }
-        if hours > 0 {
-            string = format!("{string}{}h", hours);
-        }
-        if minutes > 0 {
-            string = format!("{string}{}m", minutes);
-        }
-        if seconds > 0 {
-            string = format!("{string}{}s", seconds);
-        }
-        if milliseconds > 0 {
-            string = format!("{string}{}ms", milliseconds);
-        }
-        string
-    }
+/// The tabs saved by an earlier visit, and which one was selected.
+fn load_tabs() -> Option<(Vec<Tab>, usize)> {
+    let storage = local_storage()?;
+    let names = storage.get_item(TABS_KEY).ok()??;
+    let tabs: Vec<_> = names
+        .split('\n')
+        .enumerate()
+        .map(|(i, name)| Tab {
+            name: name.to_string(),
+            input: storage
+                .get_item(&tab_key(i))
+                .ok()
+                .flatten()
+                .unwrap_or_default(),
+        })
+        .collect();
+    let active = storage
+        .get_item(ACTIVE_TAB_KEY)
+        .ok()
+        .flatten()
+        .and_then(|active| active.parse().ok())
+        .filter(|active| *active < tabs.len())
+        .unwrap_or(0);
+    Some((tabs, active))
 }
 
-impl Record {
-    fn timestamp(timestamp: i64, offset: FixedOffset) -> Self {
-        match DateTime::<Utc>::from_timestamp(timestamp, 0) {
-            Some(d) => Self::DateTime(d.with_timezone(&offset)),
-            None => Self::None,
-        }
+/// Saves every tab. Typing only saves the tab being edited, see [`save_input`].
+fn save_tabs(tabs: &[Tab], active: usize) {
+    let Some(storage) = local_storage() else {
+        return;
+    };
+    let names: Vec<_> = tabs.iter().map(|tab| tab.name.as_str()).collect();
+    let _ = storage.set_item(TABS_KEY, &names.join("\n"));
+    let _ = storage.set_item(ACTIVE_TAB_KEY, &active.to_string());
+    for (i, tab) in tabs.iter().enumerate() {
+        let _ = storage.set_item(&tab_key(i), &tab.input);
     }
+    // Tabs are deleted one at a time, so at most one input is left over.
+    let _ = storage.remove_item(&tab_key(tabs.len()));
+}
 
-    const fn duration(duration: Duration) -> Self {
-        Self::Duration(duration)
+fn save_input(index: usize, input: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.set_item(&tab_key(index), input);
     }
+}
 
-    const fn empty() -> Self {
-        Self::None
+/// The worksheet shared through the page's URL, if any.
+fn shared_worksheet() -> Option<Permalink> {
+    Permalink::from_fragment(&web_sys::window()?.location().hash().ok()?)
+}
+
+fn clear_fragment() {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let location = window.location();
+    let (Ok(path), Ok(query)) = (location.pathname(), location.search()) else {
+        return;
+    };
+    if let Ok(history) = window.history() {
+        let _ = history.replace_state_with_url(&JsValue::NULL, "", Some(&format!("{path}{query}")));
     }
+}
+
+/// The zone remembered from an earlier visit, if any.
+fn stored_zone() -> Option<Zone> {
+    Zone::parse(&local_storage()?.get_item(ZONE_KEY).ok()??)
+}
+
+enum Msg {
+    /// The editor's new value, and the caret's UTF-16 offset in it.
+    InputValue(String, u32),
+    SuggestionKey(String),
+    AcceptSuggestion(usize),
+    CloseSuggestions,
+    CopyToClipboard,
+    /// Copies one cell or column; `what` names it in the confirmation.
+    CopyText {
+        text: String,
+        what: String,
+    },
+    Copied(Result<String, ClipboardError>),
+    HideToast,
+    CopyExport(Export),
+    DownloadExport(Export),
+    Share,
+    ToggleRelative,
+    ToggleFreeze,
+    SetZone(String),
+    SelectTab(usize),
+    NewTab,
+    DuplicateTab,
+    DeleteTab,
+    RenameTab(String),
+    Tick,
+    Evaluated(Chunk),
+    CopyColumn(Column),
+    /// Shows or hides the rows of the series on a line.
+    ToggleSeries(usize),
+    /// The page scrolled or was resized, so other rows may be visible.
+    Scroll,
+}
+
+#[derive(Clone, Copy)]
+enum Column {
+    Datetime,
+    Timestamp,
+}
 
-    fn to_datetime_string(&self) -> String {
+impl Column {
+    /// A record as it fits in a cell, which for a series is its first row.
+    fn format(self, record: &Record) -> String {
         match self {
-            Self::DateTime(datetime) => datetime.to_string(),
-            Self::Duration(duration) => duration.to_fmt_string(),
-            Self::Offset(offset) => format!("UTC{}", offset),
-            _ => "...".to_string(),
+            Self::Datetime => record.to_datetime_string(),
+            Self::Timestamp => record.to_timestamp_string(),
         }
     }
 
-    fn to_timestamp_string(&self) -> String {
+    /// Every row of a record, as copied.
+    fn text(self, record: &Record) -> String {
+        let rows: Vec<_> = record.rows().iter().map(|row| self.format(row)).collect();
+        rows.join("\n")
+    }
+
+    const fn name(self) -> &'static str {
         match self {
-            Self::DateTime(datetime) => datetime.timestamp().to_string(),
-            Self::Duration(duration) => (duration.num_milliseconds() as f64 / 1000.).to_string(),
-            Self::Offset(offset) => format!("UTC{}", offset),
-            _ => "...".to_string(),
+            Self::Datetime => "datetimes",
+            Self::Timestamp => "timestamps",
+        }
+    }
+}
+
+/// One line of the editor overlay: tokens coloured, and everything from a parse error on
+/// underlined, with what was expected there shown on hover. References in `stale` lost
+/// their line in the last edit and are flagged. A line that parses but `failed` to evaluate,
+/// such as a circular reference, is underlined whole with the reason.
+fn highlighted_line(line: &str, stale: &[Range<usize>], failed: Option<String>) -> Html {
+    let error = match check(line) {
+        None if failed.is_some() => Some(ParseError {
+            position: 0,
+            expected: vec![],
+        }),
+        error => error,
+    };
+    let error_at = error
+        .as_ref()
+        .map_or(line.len(), |error| error.position.min(line.len()));
+    let tokens = highlight(line);
+    let mut bounds = vec![0, error_at, line.len()];
+    for range in tokens.iter().map(|(range, _)| range).chain(stale) {
+        bounds.extend([range.start, range.end]);
+    }
+    bounds.sort_unstable();
+    bounds.dedup();
+    let title = failed.or_else(|| error.as_ref().map(ToString::to_string));
+    let segments = bounds.windows(2).map(|bounds| {
+        let (start, end) = (bounds[0], bounds[1]);
+        let token = tokens
+            .iter()
+            .find(|(range, _)| range.start <= start && end <= range.end)
+            .map(|(_, token)| token.name());
+        let failed = error.is_some() && start >= error_at;
+        let orphaned = stale
+            .iter()
+            .any(|range| range.start <= start && end <= range.end);
+        let title = if failed {
+            title.clone()
+        } else if orphaned {
+            Some("The line this referred to was deleted".to_string())
+        } else {
+            None
+        };
+        html! {
+            <span
+                class={classes!(token, failed.then_some("error"), orphaned.then_some("stale"))}
+                {title}
+            >{ &line[start..end] }</span>
+        }
+    });
+    // An error at the very end of the line still needs something to underline.
+    let trailing = error.is_some() && error_at == line.len();
+    html! {
+        <>
+            { for segments }
+            if trailing {
+                <span class="error" title={title.clone()}>{" "}</span>
+            }
+            {"\n"}
+        </>
+    }
+}
+
+/// The byte offset in `text` of the UTF-16 offset `units`, as used by `selectionStart`.
+fn byte_offset(text: &str, units: u32) -> usize {
+    let mut seen = 0;
+    for (i, c) in text.char_indices() {
+        if seen >= units as usize {
+            return i;
         }
+        seen += c.len_utf16();
     }
+    text.len()
+}
+
+/// Completions for the word before the caret.
+struct Suggestions {
+    /// Byte range of the input replaced by the chosen completion.
+    start: usize,
+    end: usize,
+    /// The line being edited, to place the list below it.
+    line: usize,
+    items: Vec<Completion>,
+    selected: usize,
+}
+
+async fn copy(text: String) -> Result<(), ClipboardError> {
+    let promise = write_to_clipboard(&text).ok_or(ClipboardError::NotAvailable)?;
+    JsFuture::from(promise)
+        .await
+        .map(|_| ())
+        .map_err(|_| ClipboardError::Write)
 }
 
 #[derive(Error, Debug)]
@@ -138,34 +339,314 @@ pub enum ClipboardError {
 }
 
 struct Container {
-    records: Vec<Record>,
-    input: String,
+    sheet: Worksheet,
+    /// All the worksheets. The input of the selected one is also in `sheet`.
+    tabs: Vec<Tab>,
+    active: usize,
+    /// Whether the "time ago / from now" column is shown.
+    relative: bool,
+    /// The value of `now`, refreshed every second unless frozen.
+    now: i64,
+    /// Whether `now` is paused, so the worksheet stops changing while it is inspected.
+    frozen: bool,
+    /// The zone used until the first `#UTC` header: the browser's, unless overridden.
+    zone: Zone,
+    /// Whether the zone was picked by the user rather than taken from the browser.
+    zone_overridden: bool,
+    /// Set when the last zone typed in could not be parsed.
+    zone_invalid: bool,
+    editor: NodeRef,
+    suggestions: Option<Suggestions>,
+    /// Where to put the caret after the next render, as a UTF-16 offset.
+    caret: Option<u32>,
+    /// References whose line the last edit deleted, as a line index and a byte range.
+    orphans: Vec<(usize, Range<usize>)>,
+    /// The confirmation, or error, of the last copy, and the timer that hides it.
+    toast: Option<(String, Timeout)>,
+    _clock: Interval,
+    evaluator: WorkerBridge<Evaluator>,
+    /// The last evaluation requested.
+    evaluation: u64,
+    /// The last evaluation finished, here or by the worker.
+    answered: u64,
+    /// The last evaluation done here rather than by the worker, whose answers to earlier
+    /// ones are ignored.
+    local: u64,
+    /// Whether the records are the worker's last answer, so it only sends what changed.
+    synced: bool,
+    /// The number of lines of a large input, while the worker evaluates an edit to it.
+    evaluating: Option<usize>,
+    /// The rows on screen, give or take [`OVERSCAN_ROWS`]. Only these are rendered in the
+    /// result columns and the highlighting, so long worksheets stay light on the DOM.
+    visible: Range<usize>,
+    rows: NodeRef,
+    /// The height of a line in the editor and the result columns, in pixels, as the
+    /// stylesheet's `--line-height` makes it.
+    line_height: f64,
+    _scroll: [EventListener; 2],
+    /// The line whose series is shown in full, under its cells.
+    expanded: Option<usize>,
 }
 
 impl Display for Container {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let input_lines = self.input.split('\n').map(|s| s.trim());
-        let max_length = input_lines.clone().map(|s| s.len()).max().unwrap_or(0);
-        let text = input_lines
-            .zip(
-                self.records
-                    .iter()
-                    .map(|record| record.to_datetime_string()),
-            )
-            .map(|(input, record)| format!("{input:max_length$} {record}"))
-            .collect::<Vec<_>>()
-            .join("\n");
-        write!(f, "{text}")
+        if self.relative {
+            write!(f, "{}", self.sheet.to_relative_string(self.now))
+        } else {
+            self.sheet.fmt(f)
+        }
     }
 }
 
 impl Container {
-    fn copy_to_clipboard(&self) -> Result<(), ClipboardError> {
-        if write_to_clipboard(&self.to_string()) {
-            Ok(())
+    fn evaluate(&mut self) {
+        let settings = Settings {
+            zone: self.zone,
+            ..Settings::default()
+        };
+        self.evaluation += 1;
+        let lines = self.sheet.input.split('\n').count();
+        if lines < WORKER_LINES {
+            let input = std::mem::take(&mut self.sheet.input);
+            self.sheet.update(input, self.now, settings);
+            self.answered = self.evaluation;
+            self.local = self.evaluation;
+            self.synced = false;
+            self.evaluating = None;
+            return;
+        }
+        // Until the worker answers, the lines keep their previous values.
+        self.sheet.records.resize_with(lines, Record::empty);
+        self.evaluating = Some(lines);
+        self.evaluator.send(Evaluate {
+            id: self.evaluation,
+            input: self.sheet.input.clone(),
+            now: self.now,
+            zone: settings.zone.to_string(),
+            full: !self.synced,
+        });
+        self.synced = true;
+    }
+
+    /// Works out which rows are on screen from where the rows start in the viewport.
+    /// Returns whether they changed.
+    fn update_visible(&mut self) -> bool {
+        let (Some(rows), Some(window)) = (self.rows.cast::<Element>(), web_sys::window()) else {
+            return false;
+        };
+        let height = window
+            .inner_height()
+            .ok()
+            .and_then(|height| height.as_f64())
+            .unwrap_or_default();
+        let line_height = self
+            .editor
+            .cast::<Element>()
+            .and_then(|editor| window.get_computed_style(&editor).ok()?)
+            .and_then(|style| style.get_property_value("line-height").ok())
+            .and_then(|height| height.strip_suffix("px")?.parse::<f64>().ok())
+            .filter(|height| *height > 0.0)
+            .unwrap_or(self.line_height);
+        let top = (-rows.get_bounding_client_rect().top()).max(0.0);
+        let first = (top / line_height) as usize;
+        let last = ((top + height) / line_height).ceil() as usize;
+        let visible = first.saturating_sub(OVERSCAN_ROWS)..last + OVERSCAN_ROWS;
+        let changed = visible != self.visible || line_height != self.line_height;
+        self.visible = visible;
+        self.line_height = line_height;
+        changed
+    }
+
+    /// The visible rows that exist.
+    fn visible_rows(&self) -> Range<usize> {
+        let rows = self.sheet.records.len();
+        self.visible.start.min(rows)..self.visible.end.min(rows)
+    }
+
+    /// A result column with only the visible rows in it, padded to the height of all of them
+    /// so it scrolls with the editor.
+    fn column(&self, row: impl Fn(usize, &Record) -> Html) -> Html {
+        let visible = self.visible_rows();
+        let style = format!(
+            "height: {}px; padding-top: {}px",
+            self.sheet.records.len() as f64 * self.line_height,
+            visible.start as f64 * self.line_height
+        );
+        html! {
+            <div class="rows" {style}>
+                { for visible.map(|i| row(i, &self.sheet.records[i])) }
+            </div>
+        }
+    }
+
+    /// Takes in records sent back by the worker. Answers to outdated requests are taken in
+    /// too, as the worker only sends what changed since the one before.
+    fn evaluated(&mut self, chunk: Chunk) -> bool {
+        if chunk.id < self.local {
+            return false;
+        }
+        self.sheet.records.resize_with(chunk.lines, Record::empty);
+        for (line, record) in chunk.records() {
+            self.sheet.records[line] = record;
+        }
+        self.sheet.uses_now = chunk.uses_now;
+        let last = chunk.circular.is_some();
+        if let Some(circular) = chunk.circular {
+            self.sheet.circular = circular;
+            self.sheet.cycles = chunk.cycles;
+            self.answered = chunk.id;
+        }
+        if last && chunk.id == self.evaluation {
+            self.evaluating = None;
+        }
+        true
+    }
+
+    /// Replaces the input, renumbering references to lines that moved.
+    fn set_input(&mut self, input: &str) {
+        let input_lines: Vec<_> = input.split('\n').map(|s| s.trim_start()).collect();
+        if !self.frozen {
+            self.now = now();
+        }
+        let renumbered = renumber(&self.sheet.input, &input_lines.join("\n"));
+        self.orphans = renumbered.orphans;
+        // The series shown in full stays open wherever its line went.
+        self.expanded = self
+            .expanded
+            .and_then(|line| renumbered.moved.get(line).copied().flatten());
+        self.sheet.input = renumbered.input;
+        self.tabs[self.active].input = self.sheet.input.clone();
+        save_input(self.active, &self.sheet.input);
+        self.evaluate();
+    }
+
+    /// Applies an edit typed in the editor. Renumbering can change the text before the
+    /// caret, so it is put back at the same distance from the end of its line.
+    fn edit(&mut self, input: &str, caret: u32) {
+        let at = byte_offset(input, caret);
+        let line = input[..at].matches('\n').count();
+        let line_end = input[at..].find('\n').map_or(input.len(), |i| at + i);
+        let from_end = input[at..line_end].encode_utf16().count();
+        self.set_input(input);
+        let edited = &self.sheet.input;
+        let start: usize = edited.split('\n').take(line).map(|l| l.len() + 1).sum();
+        let length = edited[start..].split('\n').next().unwrap_or_default();
+        let length = length.encode_utf16().count();
+        let head = edited[..start].encode_utf16().count();
+        let moved = (head + length.saturating_sub(from_end)) as u32;
+        if edited != input {
+            self.caret = Some(moved);
+        }
+        self.suggest(moved);
+    }
+
+    fn suggest(&mut self, caret: u32) {
+        let input = &self.sheet.input;
+        let caret = byte_offset(input, caret);
+        let line_start = input[..caret].rfind('\n').map_or(0, |i| i + 1);
+        let line = &input[line_start..caret];
+        let (before, word) = line.split_at(complete::word_start(line));
+        let after_conversion = before.ends_with(" in ") || before.ends_with(" as ");
+        let index = input[..line_start].matches('\n').count();
+        let above = &self.sheet.records[..index.min(self.sheet.records.len())];
+        let items: Vec<_> = complete::complete(before, word, above)
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .collect();
+        let finished = matches!(items.as_slice(), [only] if only.text == word);
+        self.suggestions = if (word.is_empty() && !after_conversion) || items.is_empty() || finished
+        {
+            None
         } else {
-            Err(ClipboardError::NotAvailable)
+            Some(Suggestions {
+                start: line_start + before.len(),
+                end: caret,
+                line: index,
+                items,
+                selected: 0,
+            })
+        };
+    }
+
+    fn accept_suggestion(&mut self, index: usize) {
+        let Some(suggestions) = self.suggestions.take() else {
+            return;
+        };
+        let Some(item) = suggestions.items.get(index) else {
+            return;
+        };
+        let input = &self.sheet.input;
+        let head = format!("{}{}", &input[..suggestions.start], item.text);
+        let input = format!("{head}{}", &input[suggestions.end..]);
+        self.caret = Some(head.encode_utf16().count() as u32);
+        self.set_input(&input);
+    }
+
+    fn select_tab(&mut self, index: usize) {
+        self.suggestions = None;
+        self.orphans.clear();
+        self.expanded = None;
+        self.active = index;
+        self.sheet.input = self.tabs[index].input.clone();
+        self.evaluate();
+        save_tabs(&self.tabs, self.active);
+    }
+
+    /// A name like `Sheet 3` that no tab has yet.
+    fn unused_name(&self, prefix: &str) -> String {
+        (1..)
+            .map(|i| format!("{prefix} {i}"))
+            .find(|name| self.tabs.iter().all(|tab| &tab.name != name))
+            .unwrap()
+    }
+
+    fn set_zone(&mut self, zone: &str) {
+        let storage = local_storage();
+        if zone.trim().is_empty() {
+            self.zone = browser_zone();
+            self.zone_overridden = false;
+            self.zone_invalid = false;
+            if let Some(storage) = storage {
+                let _ = storage.remove_item(ZONE_KEY);
+            }
+            return;
         }
+        match Zone::parse(zone) {
+            Some(zone) => {
+                self.zone = zone;
+                self.zone_overridden = true;
+                self.zone_invalid = false;
+                if let Some(storage) = storage {
+                    let _ = storage.set_item(ZONE_KEY, &zone.to_string());
+                }
+            }
+            None => self.zone_invalid = true,
+        }
+    }
+
+    /// A link to the worksheet, with the current `now` and zone.
+    fn share_link(&self) -> Option<String> {
+        let permalink = Permalink {
+            input: self.sheet.input.clone(),
+            now: Some(self.now),
+            zone: Some(self.zone),
+        };
+        // The page's own URL is left alone: it would import the worksheet again on reload.
+        let page = web_sys::window()?.location().href().ok()?;
+        let page = page.split('#').next().unwrap_or_default();
+        Some(format!("{page}#{}", permalink.to_fragment()))
+    }
+
+    /// Writes `text` to the clipboard, confirming with a toast naming `what` was copied.
+    fn copy_to_clipboard(&self, ctx: &Context<Self>, text: String, what: String) {
+        ctx.link()
+            .send_future(async move { Msg::Copied(copy(text).await.map(|()| what)) });
+    }
+
+    fn show_toast(&mut self, ctx: &Context<Self>, message: String) {
+        let link = ctx.link().clone();
+        let timer = Timeout::new(TOAST_MILLIS, move || link.send_message(Msg::HideToast));
+        self.toast = Some((message, timer));
     }
 }
 
@@ -173,38 +654,339 @@ impl Component for Container {
     type Message = Msg;
     type Properties = ();
 
-    fn create(_context: &Context<Self>) -> Self {
-        let record = Record::empty();
-        Self {
-            records: vec![record],
-            input: "".to_string(),
+    fn create(ctx: &Context<Self>) -> Self {
+        let link = ctx.link().clone();
+        let scroll = |event| {
+            let link = ctx.link().clone();
+            let window = web_sys::window().expect("no window");
+            EventListener::new(&window, event, move |_| link.send_message(Msg::Scroll))
+        };
+        let evaluated = ctx.link().callback(Msg::Evaluated);
+        let evaluator = Evaluator::spawner()
+            .callback(move |chunk| evaluated.emit(chunk))
+            .spawn_with_loader(EVALUATOR_LOADER);
+        let stored = stored_zone();
+        let (tabs, active) =
+            load_tabs().unwrap_or_else(|| (vec![Tab::new("Sheet 1".to_string())], 0));
+        let mut container = Self {
+            sheet: Worksheet::empty(),
+            tabs,
+            active,
+            relative: false,
+            now: now(),
+            frozen: false,
+            zone: stored.unwrap_or_else(browser_zone),
+            zone_overridden: stored.is_some(),
+            zone_invalid: false,
+            editor: NodeRef::default(),
+            suggestions: None,
+            caret: None,
+            orphans: vec![],
+            toast: None,
+            _clock: Interval::new(1000, move || link.send_message(Msg::Tick)),
+            evaluator,
+            evaluation: 0,
+            answered: 0,
+            local: 0,
+            synced: false,
+            evaluating: None,
+            visible: 0..INITIAL_ROWS,
+            line_height: INITIAL_LINE_HEIGHT,
+            rows: NodeRef::default(),
+            _scroll: [scroll("scroll"), scroll("resize")],
+            expanded: None,
+        };
+        if let Some(shared) = shared_worksheet() {
+            // Reproduce the sender's results: their clock is frozen and their zone is used,
+            // without replacing the zone remembered for this browser.
+            if let Some(now) = shared.now {
+                container.now = now;
+                container.frozen = true;
+            }
+            if let Some(zone) = shared.zone {
+                container.zone = zone;
+                container.zone_overridden = true;
+            }
+            // A shared worksheet opens in a tab of its own, leaving the saved ones alone.
+            let mut tab = Tab::new(container.unused_name("Shared"));
+            tab.input = shared.input;
+            container.tabs.push(tab);
+            container.active = container.tabs.len() - 1;
+            save_tabs(&container.tabs, container.active);
+            // It is saved now, so reloading the page should not import it a second time.
+            clear_fragment();
         }
+        container.sheet.input = container.tabs[container.active].input.clone();
+        container.evaluate();
+        container
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            Msg::InputValue(input) => {
-                self.records = parse(&input, now());
-                let input_lines: Vec<_> = input.split('\n').map(|s| s.trim_start()).collect();
-                self.input = input_lines.join("\n");
+            Msg::InputValue(input, caret) => {
+                self.edit(&input, caret);
                 true
             }
-            Msg::CopyToClipboard => match self.copy_to_clipboard() {
-                Ok(()) => true,
-                Err(_) => false,
+            Msg::SuggestionKey(key) => {
+                let Some(suggestions) = &mut self.suggestions else {
+                    return false;
+                };
+                let count = suggestions.items.len();
+                match key.as_str() {
+                    "ArrowDown" => suggestions.selected = (suggestions.selected + 1) % count,
+                    "ArrowUp" => suggestions.selected = (suggestions.selected + count - 1) % count,
+                    "Tab" | "Enter" => {
+                        let selected = suggestions.selected;
+                        self.accept_suggestion(selected);
+                    }
+                    _ => self.suggestions = None,
+                }
+                true
+            }
+            Msg::AcceptSuggestion(index) => {
+                self.accept_suggestion(index);
+                true
+            }
+            Msg::CloseSuggestions => self.suggestions.take().is_some(),
+            Msg::SelectTab(index) => {
+                self.select_tab(index);
+                true
+            }
+            Msg::NewTab => {
+                self.tabs.push(Tab::new(self.unused_name("Sheet")));
+                self.select_tab(self.tabs.len() - 1);
+                true
+            }
+            Msg::DuplicateTab => {
+                let mut tab = self.tabs[self.active].clone();
+                tab.name = self.unused_name(&format!("{} copy", tab.name));
+                self.tabs.insert(self.active + 1, tab);
+                self.select_tab(self.active + 1);
+                true
+            }
+            Msg::DeleteTab => {
+                let tab = &self.tabs[self.active];
+                // A worksheet with nothing in it is not worth asking about.
+                let confirmed = tab.input.trim().is_empty()
+                    || web_sys::window()
+                        .and_then(|window| {
+                            let message = format!("Delete the worksheet \"{}\"?", tab.name);
+                            window.confirm_with_message(&message).ok()
+                        })
+                        .unwrap_or(false);
+                if !confirmed {
+                    return false;
+                }
+                self.tabs.remove(self.active);
+                if self.tabs.is_empty() {
+                    self.tabs.push(Tab::new("Sheet 1".to_string()));
+                }
+                self.select_tab(self.active.min(self.tabs.len() - 1));
+                true
+            }
+            Msg::RenameTab(name) => {
+                // Names are stored one per line.
+                let name = name.replace('\n', " ").trim().to_string();
+                if !name.is_empty() {
+                    self.tabs[self.active].name = name;
+                    save_tabs(&self.tabs, self.active);
+                }
+                true
+            }
+            Msg::SetZone(zone) => {
+                self.set_zone(&zone);
+                self.evaluate();
+                true
+            }
+            Msg::ToggleRelative => {
+                self.relative = !self.relative;
+                true
+            }
+            Msg::ToggleFreeze => {
+                self.frozen = !self.frozen;
+                if !self.frozen {
+                    self.now = now();
+                    self.evaluate();
+                }
+                true
+            }
+            Msg::Tick => {
+                if self.frozen {
+                    return false;
+                }
+                self.now = now();
+                // Only lines using `now` change, and an evaluation still running will be
+                // followed by the next tick's.
+                let evaluate = self.sheet.uses_now && self.answered == self.evaluation;
+                if evaluate {
+                    self.evaluate();
+                    // The clock ticking refreshes large inputs quietly.
+                    self.evaluating = None;
+                }
+                // Nothing else on the page follows the clock but the relative column.
+                evaluate || self.relative
+            }
+            Msg::Evaluated(chunk) => self.evaluated(chunk),
+            Msg::CopyColumn(column) => {
+                let text: Vec<_> = self.sheet.records.iter().map(|r| column.text(r)).collect();
+                self.copy_to_clipboard(ctx, text.join("\n"), column.name().to_string());
+                false
+            }
+            Msg::ToggleSeries(line) => {
+                self.expanded = (self.expanded != Some(line)).then_some(line);
+                true
+            }
+            Msg::Scroll => self.update_visible(),
+            Msg::CopyToClipboard => {
+                self.copy_to_clipboard(ctx, self.to_string(), "worksheet".to_string());
+                false
+            }
+            Msg::CopyText { text, what } => {
+                self.copy_to_clipboard(ctx, text, what);
+                false
+            }
+            Msg::Copied(result) => {
+                let message = match result {
+                    Ok(what) => format!("Copied {what}"),
+                    Err(e) => e.to_string(),
+                };
+                self.show_toast(ctx, message);
+                true
+            }
+            Msg::HideToast => {
+                self.toast = None;
+                true
+            }
+            Msg::CopyExport(export) => {
+                let what = format!("worksheet as {}", export.name());
+                self.copy_to_clipboard(ctx, export.write(&self.sheet), what);
+                false
+            }
+            Msg::DownloadExport(export) => {
+                let name = &self.tabs[self.active].name;
+                let filename = format!("{name}.{}", export.extension());
+                download(&filename, &export.write(&self.sheet), export.mime_type());
+                false
+            }
+            Msg::Share => match self.share_link() {
+                Some(link) => {
+                    self.copy_to_clipboard(ctx, link, "link".to_string());
+                    false
+                }
+                None => {
+                    self.show_toast(ctx, "Could not make a link to this page".to_string());
+                    true
+                }
             },
         }
     }
 
+    fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
+        if first_render {
+            ctx.link().send_message(Msg::Scroll);
+        }
+        // Setting the textarea's value moves the caret to the end, so put it back after
+        // the word a completion was inserted for.
+        if let Some(caret) = self.caret.take()
+            && let Some(editor) = self.editor.cast::<HtmlTextAreaElement>()
+        {
+            let _ = editor.set_selection_range(caret, caret);
+        }
+    }
+
     fn view(&self, ctx: &Context<Self>) -> Html {
         let title = "Unix Time Calculator".to_string();
         let link = ctx.link();
 
         let on_input = link.callback(|e: InputEvent| {
-            Msg::InputValue(e.target_unchecked_into::<HtmlInputElement>().value())
+            let editor = e.target_unchecked_into::<HtmlTextAreaElement>();
+            let caret = editor.selection_start().ok().flatten().unwrap_or(0);
+            Msg::InputValue(editor.value(), caret)
         });
+        let suggesting = self.suggestions.is_some();
+        let on_keydown = link.batch_callback(move |e: KeyboardEvent| {
+            let key = e.key();
+            let handled = ["ArrowDown", "ArrowUp", "Tab", "Enter", "Escape"];
+            if suggesting && handled.contains(&key.as_str()) {
+                e.prevent_default();
+                Some(Msg::SuggestionKey(key))
+            } else {
+                None
+            }
+        });
+        let on_blur = link.callback(|_| Msg::CloseSuggestions);
 
         let copy_to_clipboard = link.callback(|_| Msg::CopyToClipboard);
+        let share = link.callback(|_| Msg::Share);
+        let cell = |column: Column| {
+            move |i: usize, record: &Record| {
+                let copyable = |text: String| {
+                    let copy = {
+                        let text = text.clone();
+                        link.callback(move |_| Msg::CopyText {
+                            text: text.clone(),
+                            what: format!("line {}", i + 1),
+                        })
+                    };
+                    html! {
+                        <div class="cell" title="Copy" onclick={copy}>{ text }</div>
+                    }
+                };
+                let Record::Series(rows) = record else {
+                    return copyable(column.format(record));
+                };
+                // A series has more rows than its line, so they open under it on demand.
+                let expanded = self.expanded == Some(i);
+                let toggle = link.callback(move |_| Msg::ToggleSeries(i));
+                html! {
+                    <div class="series">
+                        <div
+                            class="cell"
+                            title={if expanded { "Hide the rows" } else { "Show every row" }}
+                            onclick={toggle}
+                        >{ column.format(record) }</div>
+                        if expanded {
+                            <div class="series-rows">
+                                { for rows.iter().map(|row| copyable(column.format(row))) }
+                            </div>
+                        }
+                    </div>
+                }
+            }
+        };
+        let copy_column = |column: Column| link.callback(move |_| Msg::CopyColumn(column));
+        let visible = self.visible_rows();
+        let toggle_relative = link.callback(|_| Msg::ToggleRelative);
+        let toggle_freeze = link.callback(|_| Msg::ToggleFreeze);
+        let on_zone = link.callback(|e: Event| {
+            Msg::SetZone(e.target_unchecked_into::<HtmlInputElement>().value())
+        });
+        let on_rename = link.callback(|e: Event| {
+            Msg::RenameTab(e.target_unchecked_into::<HtmlInputElement>().value())
+        });
+        let new_tab = link.callback(|_| Msg::NewTab);
+        let duplicate_tab = link.callback(|_| Msg::DuplicateTab);
+        let delete_tab = link.callback(|_| Msg::DeleteTab);
+        let zone_class = if self.zone_invalid {
+            "zone-input invalid"
+        } else {
+            "zone-input"
+        };
+        let zone_source = if self.zone_overridden {
+            "chosen"
+        } else {
+            "from your browser"
+        };
+        let (freeze_icon, freeze_title) = if self.frozen {
+            ("fa-solid resume", "Now is frozen: resume the clock")
+        } else {
+            ("fa-solid freeze", "Freeze now")
+        };
+        let container_class = if self.relative {
+            "container with-relative"
+        } else {
+            "container"
+        };
 
         html! {
             <div>
@@ -219,47 +1001,154 @@ impl Component for Container {
                     </div>
 
                     <div class="app">
-                        <div class="container">
-                            <div class="line-number">
-                                <div> {
-                                    for (1..=self.records.len()).map(|i| {
-                                        html!{
-                                            <div>{i}</div>
-                                        } })
+                        <div class="tabs">
+                            {
+                                for self.tabs.iter().enumerate().map(|(i, tab)| {
+                                    if i == self.active {
+                                        html! {
+                                            <input
+                                                class="tab active"
+                                                value={tab.name.clone()}
+                                                onchange={on_rename.clone()}
+                                                title="Rename this worksheet"
+                                            />
+                                        }
+                                    } else {
+                                        let select = link.callback(move |_| Msg::SelectTab(i));
+                                        html! {
+                                            <button class="tab" onclick={select}>{ tab.name.clone() }</button>
+                                        }
                                     }
-                                </div>
+                                })
+                            }
+                            <button class="tab-action" title="New worksheet" onclick={new_tab}>{"+"}</button>
+                            <button class="tab-action" title="Duplicate this worksheet" onclick={duplicate_tab}>{"⧉"}</button>
+                            <button class="tab-action" title="Delete this worksheet" onclick={delete_tab}>{"×"}</button>
+                        </div>
+                        <div class="zone">
+                            <label for="zone">{"Zone"}</label>
+                            <input
+                                id="zone"
+                                class={zone_class}
+                                value={self.zone.to_string()}
+                                onchange={on_zone}
+                                placeholder="Europe/Paris, UTC+5"
+                                title="Zone used until the first #UTC line. Clear it to use your browser's."
+                            />
+                            <span>{ format!("UTC{}, {zone_source}", self.zone.offset_at(self.now)) }</span>
+                            if let Some(lines) = self.evaluating {
+                                <span class="progress">
+                                    <progress />
+                                    { format!("Evaluating {lines} lines") }
+                                </span>
+                            }
+                        </div>
+                        <div class={container_class} ref={self.rows.clone()}>
+                            <div class="line-number">
+                                { self.column(|i, _| html! { <div>{ i + 1 }</div> }) }
                             </div>
                             <div class="input-text">
+                                <pre
+                                    class="highlight"
+                                    aria-hidden="true"
+                                    style={format!("padding-top: {}px", visible.start as f64 * self.line_height)}
+                                >
+                                    { for self.sheet.input.split('\n').enumerate().skip(visible.start).take(visible.len()).map(|(i, line)| {
+                                    let stale: Vec<_> = self
+                                        .orphans
+                                        .iter()
+                                        .filter(|(line, _)| *line == i)
+                                        .map(|(_, range)| range.clone())
+                                        .collect();
+                                    let failed = if self.sheet.cycles.contains(&i) {
+                                        Some("This line refers back to itself through its references".to_string())
+                                    } else if self.sheet.circular.contains(&i) {
+                                        Some("This line depends on a circular reference".to_string())
+                                    } else if let Some(Record::Error(error)) = self.sheet.records.get(i) {
+                                        Some(error.clone())
+                                    } else {
+                                        None
+                                    };
+                                    highlighted_line(line, &stale, failed)
+                                }) }
+                                </pre>
                                 <textarea
+                                ref={self.editor.clone()}
                                 oninput={on_input}
-                                value={self.input.clone()}
+                                onkeydown={on_keydown}
+                                onblur={on_blur}
+                                value={self.sheet.input.clone()}
                                 class="input-textarea"
                                 style="resize: none"
                                 data-gramm="false"
                                 placeholder=""
                                 wrap = "off"
                                 />
+                                if let Some(suggestions) = &self.suggestions {
+                                    <ul
+                                        class="suggestions"
+                                        style={format!("top: {}px", (suggestions.line + 1) as f64 * self.line_height)}
+                                    >
+                                        {
+                                            for suggestions.items.iter().enumerate().map(|(i, item)| {
+                                                let accept = link.callback(move |e: MouseEvent| {
+                                                    // Keep the focus, and so the caret, in the editor.
+                                                    e.prevent_default();
+                                                    Msg::AcceptSuggestion(i)
+                                                });
+                                                html! {
+                                                    <li
+                                                        class={classes!((i == suggestions.selected).then_some("selected"))}
+                                                        onmousedown={accept}
+                                                    >
+                                                        <span>{ &item.text }</span>
+                                                        <span class="detail">{ &item.detail }</span>
+                                                    </li>
+                                                }
+                                            })
+                                        }
+                                    </ul>
+                                }
                             </div>
                             <div class="date-format">
-                                <div> {
-                                    for self.records.iter().map(|v| {
-                                        html!{
-                                            <div>{ v.to_datetime_string() }</div>
-                                        } })
-                                    }
-                                </div>
+                            <button class="btn" title="Copy all datetimes" onclick={copy_column(Column::Datetime)}><i class="fa-solid column-copy"></i></button>
+                                { self.column(cell(Column::Datetime)) }
                             </div>
+                            if self.relative {
+                                <div class="relative">
+                                    { self.column(|_, record| html! { <div>{ record.to_relative_string(self.now) }</div> }) }
+                                </div>
+                            }
                             <div class="timestamp">
                             <button class="btn" onclick={copy_to_clipboard}><i class="fa-solid clipboard"></i></button>
-                                <div> {
-                                    for self.records.iter().map(|v| {
-                                        html!{
-                                            <div>{ v.to_timestamp_string() }</div>
-                                        } })
-                                    }
-                                </div>
+                            <button class="btn" title="Copy a link to this worksheet" onclick={share}><i class="fa-solid share"></i></button>
+                            <button class="btn" title="Show time ago / from now" onclick={toggle_relative}><i class="fa-solid relative-toggle"></i></button>
+                            <button class="btn" title={freeze_title} onclick={toggle_freeze}><i class={freeze_icon}></i></button>
+                            <button class="btn" title="Copy all timestamps" onclick={copy_column(Column::Timestamp)}><i class="fa-solid column-copy"></i></button>
+                                { self.column(cell(Column::Timestamp)) }
                             </div>
                         </div>
+                        if let Some((message, _)) = &self.toast {
+                            <div class="toast">{ message }</div>
+                        }
+                        <details class="export">
+                            <summary>{"Export"}</summary>
+                            <table class="help-table">
+                                {
+                                    for Export::ALL.into_iter().map(|export| {
+                                        let copy = link.callback(move |_| Msg::CopyExport(export));
+                                        let download = link.callback(move |_| Msg::DownloadExport(export));
+                                        html! {
+                                            <tr>
+                                                <td>{ export.name() }</td>
+                                                <td><button class="export-action" onclick={copy}>{"copy"}</button></td>
+                                                <td><button class="export-action" onclick={download}>{"download"}</button></td>
+                                            </tr>
+                                        }
+                                    })
+                                }
+                            </table>
+                        </details>
                         <details class="help">
                             <summary>{"Syntax"}</summary>
                             <table class="help-table">
@@ -269,8 +1158,19 @@ impl Component for Container {
                                 <tr><td>{"2024-01-15 12:00:00 +05:00"}</td><td>{"With UTC offset"}</td></tr>
                                 <tr><td>{"2h30m, 1.5d, 90s, 500ms"}</td><td>{"Duration (d h m s ms)"}</td></tr>
                                 <tr><td>{"now - 7d"}</td><td>{"Arithmetic: + and −"}</td></tr>
+                                <tr><td>{"#1..#2, '2024-01-01' .. +3d"}</td><td>{"Interval; .start, .end, .length"}</td></tr>
+                                <tr><td>{"#3 contains now, overlaps, intersect, union, gap"}</td><td>{"Compare intervals"}</td></tr>
+                                <tr><td>{"series(now, 15m, 16)"}</td><td>{"Datetimes a step apart, one row each"}</td></tr>
+                                <tr><td>{"'2024-01-01' every 1mo until '2025-01-01'"}</td><td>{"Series up to an end; mo and y step by months"}</td></tr>
+                                <tr><td>{"1 + 2, now - 60"}</td><td>{"Plain numbers; seconds next to a datetime"}</td></tr>
                                 <tr><td>{"#2 - #1"}</td><td>{"Reference a previous line"}</td></tr>
+                                <tr><td>{"#-1, #prev, #first, #last-timestamp"}</td><td>{"Reference a line relative to this one"}</td></tr>
+                                <tr><td>{"now in Asia/Tokyo, #3 as iso"}</td><td>{"Show one line in another zone or format"}</td></tr>
+                                <tr><td>{"#2 - #1 in minutes, now in ms"}</td><td>{"Show as a number of units"}</td></tr>
+                                <tr><td>{"$2, _"}</td><td>{"Same as #2; the previous line"}</td></tr>
+                                <tr><td>{"#NOW 2024-01-15 12:00:00"}</td><td>{"Pin now for lines below"}</td></tr>
                                 <tr><td>{"#UTC+5, #UTC-8"}</td><td>{"Set timezone for lines below"}</td></tr>
+                                <tr><td>{"#FORMAT iso, #FORMAT %d/%m %H:%M"}</td><td>{"Set datetime format for lines below"}</td></tr>
                             </table>
                         </details>
 
@@ -284,23 +1184,3 @@ impl Component for Container {
 fn main() {
     yew::Renderer::<Container>::new().render();
 }
-
-#[cfg(test)]
-mod test {
-    use super::*;
-    #[test]
-    fn test() {
-        let input: String = "#UTC+1\n12323123\n'1970-05-23 16:05:23'".to_string();
-        let records = parse(&input, 1);
-        assert_eq!(records.len(), 3);
-        let container = Container { records, input };
-        assert_eq!(
-            container.to_string(),
-            concat!(
-                "#UTC+1                UTC+01:00\n",
-                "12323123              1970-05-23 16:05:23 +01:00\n",
-                "'1970-05-23 16:05:23' 1970-05-23 16:05:23 +01:00"
-            ),
-        );
-    }
-}
@@ -1,14 +1,35 @@
 extern crate peg;
-use std::ops::{Add, Sub};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Add, Range, Sub};
 use std::panic;
+use std::sync::LazyLock;
 
-use chrono::{Duration, FixedOffset, LocalResult, Offset, TimeZone, Utc};
+use chrono::{DateTime, Duration, FixedOffset, Months, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
 use peg::parser;
 use regex::Regex;
 
+use crate::format::{Conversion, DateFormat};
+use crate::graph::{Dependencies, Graph, Reference};
+
+/// The most values a series can have, so a typo like `every 1s` cannot freeze the page.
+pub const MAX_SERIES: usize = 10_000;
+
+/// `#UTC+N` headers.
+static OFFSET_HEADER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^#UTC([+-])(\d{1,2})$").unwrap());
+
+/// Offsets as accepted by [`parse_offset`].
+static OFFSET: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?i:UTC|Z)?(?:([+-])(\d{1,2})(?::?(\d{2}))?)?$").unwrap());
+
+/// The keys of JSON objects, which are dropped so pasted JSON evaluates to its values.
+static JSON_KEY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"['"]*\s*[[:alnum:]_\s*]+?\s*\s*['"]:"#).unwrap());
+
 fn get_time_zone(input: &str) -> Option<FixedOffset> {
-    let re = Regex::new(r"^#UTC([+-])(\d{1,2})$").unwrap();
-    match re.captures(input.trim()) {
+    match OFFSET_HEADER.captures(input.trim()) {
         Some(x) => {
             if x.len() != 3 {
                 return None;
@@ -28,71 +49,623 @@ fn get_time_zone(input: &str) -> Option<FixedOffset> {
     }
 }
 
+fn get_format(input: &str) -> Option<DateFormat> {
+    let format = input.trim().strip_prefix("#FORMAT")?;
+    if !format.starts_with(' ') {
+        return None;
+    }
+    DateFormat::parse(format.trim())
+}
+
+/// `#NOW <time>` pins `now` for the lines below, e.g. `#NOW 2024-01-15 12:00:00`.
+fn get_now(input: &str, state: &State) -> Option<i64> {
+    let now = input.trim().strip_prefix("#NOW")?;
+    if !now.starts_with(' ') {
+        return None;
+    }
+    match arithmetic::value(now.trim(), state) {
+        Ok(Expression::Timestamp(timestamp)) => Some(timestamp),
+        _ => None,
+    }
+}
+
+/// Parses a UTC offset written as `UTC`, `UTC+5`, `-8` or `+05:30`.
+pub fn parse_offset(input: &str) -> Option<FixedOffset> {
+    let x = OFFSET.captures(input.trim())?;
+    let Some(sign) = x.get(1) else {
+        return Some(Utc.fix());
+    };
+    let hours: i32 = x[2].parse().ok()?;
+    let minutes: i32 = x.get(3).map_or(Some(0), |m| m.as_str().parse().ok())?;
+    if minutes >= 60 {
+        return None;
+    }
+    let seconds = hours * 3600 + minutes * 60;
+    match sign.as_str() {
+        "+" => FixedOffset::east_opt(seconds),
+        _ => FixedOffset::east_opt(-seconds),
+    }
+}
+
+/// Finds the epoch timestamps in a line of free text, such as a log line, together
+/// with their byte ranges. Unlike [`parse`] this never evaluates the text.
+pub fn find_epochs(text: &str) -> Vec<(Range<usize>, i64)> {
+    let state = State::new(Utc.fix(), 0, &[]);
+    arithmetic::epochs(text, &state).unwrap_or_default()
+}
+
+/// What a span of a line is, for syntax highlighting.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Token {
+    /// A header such as `#UTC+1`, `#FORMAT` or `#NOW`.
+    Header,
+    /// `#N`, `$N` or `_`.
+    Reference,
+    Number,
+    Duration,
+    DateTime,
+    Now,
+    Operator,
+    /// `as` or `in`.
+    Keyword,
+    /// What follows `as` or `in`.
+    Conversion,
+}
+
+impl Token {
+    /// A short lowercase name, e.g. for CSS classes.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Header => "header",
+            Self::Reference => "reference",
+            Self::Number => "number",
+            Self::Duration => "duration",
+            Self::DateTime => "datetime",
+            Self::Now => "now",
+            Self::Operator => "operator",
+            Self::Keyword => "keyword",
+            Self::Conversion => "conversion",
+        }
+    }
+}
+
+/// The highlighted spans of a line, by byte range. Text that is not part of any token,
+/// such as spaces or JSON keys, is left out.
+pub fn highlight(line: &str) -> Vec<(Range<usize>, Token)> {
+    let state = State::new(Utc.fix(), 0, &[]);
+    panic::catch_unwind(|| arithmetic::tokens(line, &state).unwrap_or_default()).unwrap_or_default()
+}
+
+/// Where a line stops making sense, and what would have been accepted there.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseError {
+    /// Byte offset in the line.
+    pub position: usize,
+    pub expected: Vec<String>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.expected.as_slice() {
+            [] => write!(f, "unexpected input"),
+            [expected] => write!(f, "expected {expected}"),
+            expected => write!(f, "expected one of {}", expected.join(", ")),
+        }
+    }
+}
+
+/// What the parser expected, in words where its name for it is a character pattern.
+fn describe(token: &str) -> String {
+    match token {
+        "['0'..='9']" => "a digit",
+        "[' ']" => "a space",
+        "['+' | '-']" => "a sign",
+        "['a'..='z' | 'A'..='Z']" | "['A'..='Z']" | "['a'..='z' | '-']" => "a letter",
+        "['0'..='9' | 'a'..='z' | 'A'..='Z' | '_']" => "a letter or digit",
+        "[^' ']" => "a word",
+        "[_]" => "anything",
+        "EOF" => "the end of the line",
+        r#"r".""# => r#"".""#,
+        token => token,
+    }
+    .to_string()
+}
+
+/// Checks that a line parses, without evaluating it. Blank lines and headers are fine;
+/// a line that parses but has no value, such as a reference to a missing line, is too.
+pub fn check(line: &str) -> Option<ParseError> {
+    let cleaned = remove_json_keys(line);
+    let input = cleaned
+        .trim()
+        .trim_start_matches(['{', ' '])
+        .trim_end_matches([';', ',', ':', '}', ' ']);
+    if input.is_empty() || get_time_zone(input).is_some() || get_format(input).is_some() {
+        return None;
+    }
+    let state = State::new(Utc.fix(), 0, &[]);
+    if get_now(input, &state).is_some() {
+        return None;
+    }
+    let error = panic::catch_unwind(|| arithmetic::line(input, &state).err()).ok()??;
+    // Positions are only meaningful when no JSON key was removed before parsing.
+    let position = match cleaned == line {
+        true => input.as_ptr() as usize - cleaned.as_ptr() as usize + error.location.offset,
+        false => 0,
+    };
+    let mut expected: Vec<_> = error.expected.tokens().map(describe).collect();
+    expected.sort();
+    expected.dedup();
+    Some(ParseError { position, expected })
+}
+
+/// A zone given either as a fixed UTC offset or as an IANA name such as `Asia/Tokyo`,
+/// whose offset depends on the instant because of daylight saving time.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Zone {
+    Fixed(FixedOffset),
+    Named(Tz),
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Fixed(offset) => write!(f, "UTC{offset}"),
+            Self::Named(tz) => f.write_str(tz.name()),
+        }
+    }
+}
+
+impl From<FixedOffset> for Zone {
+    fn from(offset: FixedOffset) -> Self {
+        Self::Fixed(offset)
+    }
+}
+
+impl Zone {
+    /// Parses anything [`parse_offset`] accepts, or an IANA zone name.
+    pub fn parse(input: &str) -> Option<Self> {
+        match parse_offset(input) {
+            Some(offset) => Some(Self::Fixed(offset)),
+            None => input.trim().parse().ok().map(Self::Named),
+        }
+    }
+
+    /// The timestamp of a local date and time in this zone, the earlier one if daylight
+    /// saving time makes it ambiguous. `None` if it does not exist in the zone.
+    fn timestamp(&self, ymd: (i32, u32, u32), hms: (u32, u32, u32)) -> Option<i64> {
+        let (year, month, day) = ymd;
+        let (hour, minute, second) = hms;
+        match self {
+            Self::Fixed(offset) => offset
+                .with_ymd_and_hms(year, month, day, hour, minute, second)
+                .single()
+                .map(|datetime| datetime.timestamp()),
+            Self::Named(tz) => tz
+                .with_ymd_and_hms(year, month, day, hour, minute, second)
+                .earliest()
+                .map(|datetime| datetime.timestamp()),
+        }
+    }
+
+    /// `start` moved by calendar `months` in this zone.
+    fn add_months(&self, start: i64, months: Months) -> Option<i64> {
+        let start = DateTime::<Utc>::from_timestamp(start, 0)?;
+        match self {
+            Self::Fixed(offset) => start.with_timezone(offset).checked_add_months(months),
+            Self::Named(tz) => start
+                .with_timezone(tz)
+                .checked_add_months(months)
+                .map(|datetime| datetime.fixed_offset()),
+        }
+        .map(|datetime| datetime.timestamp())
+    }
+
+    /// The UTC offset of this zone at `timestamp`.
+    pub fn offset_at(&self, timestamp: i64) -> FixedOffset {
+        match self {
+            Self::Fixed(offset) => *offset,
+            Self::Named(tz) => match DateTime::<Utc>::from_timestamp(timestamp, 0) {
+                Some(datetime) => datetime.with_timezone(tz).offset().fix(),
+                None => Utc.fix(),
+            },
+        }
+    }
+}
+
 pub fn parse(input: &str, now: i64) -> Vec<Record> {
-    let mut records = vec![];
-    let mut offset = Utc.fix();
-    let split = input.split('\n');
-    for line in split {
-        let expression = safe_parse_line(line, offset, now, &records);
-        records.push(Record { offset, expression });
-        offset = match expression {
-            Expression::Offset(offset) => offset,
-            _ => offset,
+    parse_with(input, now, Settings::default())
+}
+
+/// Like [`parse`], but starting from `settings` instead of UTC and the default format.
+pub fn parse_with(input: &str, now: i64, settings: Settings) -> Vec<Record> {
+    evaluate(input, now, settings).records
+}
+
+/// Every line of a worksheet evaluated, in dependency order.
+pub struct Evaluation {
+    pub records: Vec<Record>,
+    /// Lines left unevaluated because they refer to themselves, directly or through
+    /// other lines.
+    pub circular: Vec<usize>,
+    /// The lines of `circular` in a cycle themselves rather than depending on one.
+    pub cycles: Vec<usize>,
+}
+
+/// Evaluates every line after the lines it refers to, so references can point below
+/// them. See [`Graph`] for what a line depends on.
+pub fn evaluate(input: &str, now: i64, settings: Settings) -> Evaluation {
+    evaluate_cached(input, now, settings, &mut Cache::default())
+}
+
+/// The lines of the last evaluation, and what their values were computed from.
+#[derive(Default)]
+pub struct Cache {
+    lines: Vec<Cached>,
+}
+
+impl Cache {
+    /// Whether any line may use the current time, and so change as the clock ticks.
+    pub fn uses_now(&self) -> bool {
+        self.lines.iter().any(|line| line.dependencies.now)
+    }
+}
+
+struct Cached {
+    text: String,
+    dependencies: Dependencies,
+    /// `None` for lines that were not evaluated.
+    inputs: Option<Inputs>,
+    record: Record,
+}
+
+/// Everything besides its text that the value of a line was computed from.
+#[derive(PartialEq)]
+struct Inputs {
+    settings: Settings,
+    /// The current time, for lines that may use it.
+    now: Option<i64>,
+    /// The values of the lines its references found, in order.
+    values: Vec<Expression>,
+}
+
+/// Like [`evaluate`], but only evaluates the lines that changed since the evaluation
+/// kept in `cache`, or whose references now find different values. Keeps this
+/// evaluation in `cache` for the next one.
+pub fn evaluate_cached(input: &str, now: i64, settings: Settings, cache: &mut Cache) -> Evaluation {
+    let lines: Vec<_> = input.split('\n').collect();
+    let previous = std::mem::take(&mut cache.lines);
+    let matches = match_lines(&previous, &lines);
+    let dependencies: Vec<_> = lines
+        .iter()
+        .zip(&matches)
+        .map(|(line, cached)| match cached {
+            Some(i) => previous[*i].dependencies.clone(),
+            None => Dependencies::new(line),
+        })
+        .collect();
+    let graph = Graph::with_dependencies(&dependencies);
+    let order = graph.order();
+    let mut records: Vec<_> = lines
+        .iter()
+        .map(|_| Record::unevaluated(&settings))
+        .collect();
+    let mut inputs: Vec<Option<Inputs>> = lines.iter().map(|_| None).collect();
+    let mut expressions = vec![Expression::None; lines.len()];
+    for &line in &order.lines {
+        let settings = match graph.header(line) {
+            Some(header) => records[header].next_settings(),
+            None => settings.clone(),
+        };
+        let state = State::at(
+            settings.zone,
+            settings.now.unwrap_or(now),
+            &expressions,
+            line,
+        );
+        let values = dependencies[line]
+            .references
+            .iter()
+            .map(|reference| state.resolve(*reference))
+            .collect();
+        let used = Inputs {
+            now: dependencies[line].now.then_some(state.now),
+            settings,
+            values,
+        };
+        let record = match matches[line].map(|i| &previous[i]) {
+            Some(cached) if cached.inputs.as_ref() == Some(&used) => cached.record.clone(),
+            _ => evaluate_line(lines[line], &used.settings, now, &expressions, line),
         };
+        expressions[line] = (&record).into();
+        records[line] = record;
+        inputs[line] = Some(used);
+    }
+    for &line in &order.circular {
+        if let Some(header) = graph.header(line) {
+            records[line] = Record::unevaluated(&records[header].next_settings());
+        }
+    }
+    cache.lines = lines
+        .iter()
+        .zip(dependencies)
+        .zip(inputs)
+        .zip(&records)
+        .map(|(((text, dependencies), inputs), record)| Cached {
+            text: text.to_string(),
+            dependencies,
+            inputs,
+            record: record.clone(),
+        })
+        .collect();
+    Evaluation {
+        records,
+        circular: order.circular,
+        cycles: order.cycles,
     }
-    records
 }
 
-fn remove_json_keys(input: &str) -> String {
-    let re = Regex::new(r#"['"]*\s*[[:alnum:]_\s*]+?\s*\s*['"]:"#).unwrap();
-    re.replace_all(input, "").to_string()
+/// For every line, a line of the last evaluation with the same text, if any: the same
+/// line when only lines around it changed, or else the first with that text.
+fn match_lines(previous: &[Cached], lines: &[&str]) -> Vec<Option<usize>> {
+    let same = |i: usize, j: usize| previous[i].text == lines[j];
+    let shortest = previous.len().min(lines.len());
+    let prefix = (0..shortest).take_while(|&i| same(i, i)).count();
+    let suffix = (0..shortest - prefix)
+        .take_while(|&k| same(previous.len() - 1 - k, lines.len() - 1 - k))
+        .count();
+    let mut matches = vec![None; lines.len()];
+    for (i, matched) in matches.iter_mut().enumerate().take(prefix) {
+        *matched = Some(i);
+    }
+    for k in 0..suffix {
+        matches[lines.len() - 1 - k] = Some(previous.len() - 1 - k);
+    }
+    let mut texts = HashMap::new();
+    for (i, cached) in previous
+        .iter()
+        .enumerate()
+        .take(previous.len() - suffix)
+        .skip(prefix)
+    {
+        texts.entry(cached.text.as_str()).or_insert(i);
+    }
+    for j in prefix..lines.len() - suffix {
+        matches[j] = texts.get(lines[j]).copied();
+    }
+    matches
 }
 
-fn parse_line(input: &str, offset: FixedOffset, now: i64, records: &[Record]) -> Expression {
+/// Evaluates a single line as if it followed `records`, so `#N` references resolve
+/// against them. `settings` are the ones in effect for the line.
+pub fn parse_next(line: &str, settings: &Settings, now: i64, records: &[Record]) -> Record {
     let expressions: Vec<Expression> = records.iter().map(std::convert::Into::into).collect();
-    let state = State::new(offset, now, &expressions);
+    evaluate_line(line, settings, now, &expressions, records.len())
+}
+
+/// Evaluates line `index` of a worksheet whose lines evaluated so far are `expressions`.
+fn evaluate_line(
+    line: &str,
+    settings: &Settings,
+    now: i64,
+    expressions: &[Expression],
+    index: usize,
+) -> Record {
+    let now = settings.now.unwrap_or(now);
+    let state = State::at(settings.zone, now, expressions, index);
+    let (expression, conversion) = safe_parse_line(line, &state);
+    Record {
+        zone: settings.zone,
+        format: settings.format.clone(),
+        now: settings.now,
+        expression,
+        conversion,
+    }
+}
+
+fn remove_json_keys(input: &str) -> String {
+    JSON_KEY.replace_all(input, "").to_string()
+}
+
+fn parse_line(input: &str, state: &State) -> (Expression, Option<Conversion>) {
     let input = remove_json_keys(input);
     let input = input
         .trim()
         .trim_start_matches(['{', ' '])
         .trim_end_matches([';', ',', ':', '}', ' ']);
-    match arithmetic::expression(input, &state) {
+    match arithmetic::line(input, state) {
         Ok(result) => result,
-        _ => match get_time_zone(input) {
-            Some(offset) => Expression::Offset(offset),
-            _ => Expression::None,
+        _ => match (get_time_zone(input), get_format(input)) {
+            (Some(offset), _) => (Expression::Offset(offset), None),
+            (_, Some(format)) => (Expression::Format(format), None),
+            _ => match get_now(input, state) {
+                Some(now) => (Expression::Now(now), None),
+                None => (Expression::None, None),
+            },
         },
     }
 }
 
-fn safe_parse_line(input: &str, offset: FixedOffset, now: i64, records: &[Record]) -> Expression {
-    let result = panic::catch_unwind(|| parse_line(input, offset, now, records));
+fn safe_parse_line(input: &str, state: &State) -> (Expression, Option<Conversion>) {
+    let result = panic::catch_unwind(|| parse_line(input, state));
     match result {
         Ok(result) => result,
-        _ => Expression::None,
+        _ => (Expression::None, None),
     }
 }
 
 impl From<&Record> for Expression {
     fn from(record: &Record) -> Self {
-        record.expression
+        match record.expression {
+            // A `#NOW` line is referenced like any other point in time.
+            Self::Now(now) => Self::Timestamp(now),
+            ref expression => expression.clone(),
+        }
     }
 }
 
+/// What `#UTC`, `#FORMAT` and `#NOW` headers change for all the lines below them.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Settings {
+    /// The zone datetimes are shown and read in, whose offset may depend on the instant.
+    pub zone: Zone,
+    pub format: DateFormat,
+    /// The value of `now` pinned with `#NOW`, or `None` to use the one given to [`parse`].
+    pub now: Option<i64>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            zone: Zone::Fixed(Utc.fix()),
+            format: DateFormat::default(),
+            now: None,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Record {
-    pub offset: FixedOffset,
+    pub zone: Zone,
+    pub format: DateFormat,
+    pub now: Option<i64>,
     pub expression: Expression,
+    /// How this line alone is displayed, from a trailing `as`/`in`. It does not change
+    /// the value other lines see through references.
+    pub conversion: Option<Conversion>,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+impl Record {
+    /// A line not evaluated (yet), with the settings in effect for it.
+    fn unevaluated(settings: &Settings) -> Self {
+        Self {
+            zone: settings.zone,
+            format: settings.format.clone(),
+            now: settings.now,
+            expression: Expression::None,
+            conversion: None,
+        }
+    }
+
+    /// The settings in effect for the line after this one.
+    pub fn next_settings(&self) -> Settings {
+        let mut settings = Settings {
+            zone: self.zone,
+            format: self.format.clone(),
+            now: self.now,
+        };
+        match &self.expression {
+            Expression::Offset(offset) => settings.zone = Zone::Fixed(*offset),
+            Expression::Format(format) => settings.format = format.clone(),
+            Expression::Now(now) => settings.now = Some(*now),
+            _ => {}
+        }
+        settings
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
 #[non_exhaustive]
 pub enum Expression {
     Offset(FixedOffset),
+    Format(DateFormat),
+    /// A `#NOW` header, pinning `now` for the lines below.
+    Now(i64),
     Duration(Duration),
     Timestamp(i64),
+    /// A dimensionless number, such as `1 + 2`. A line that is just a number is an epoch
+    /// instead, see [`arithmetic::value`].
+    Number(f64),
+    /// The timestamps from `start` up to, but not including, `end`, such as `#1..#2`.
+    Interval(i64, i64),
+    /// Whether an interval `contains` or `overlaps` something.
+    Boolean(bool),
+    /// Points in time from a series, such as `series(now, 15m, 16)`, each shown on a row
+    /// of its own.
+    Series(Vec<i64>),
+    /// Why a line has no value, such as adding two timestamps.
+    Error(EvalError),
     None,
 }
 
+/// What kind of value an operand is, for [`TypeError`]s.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    Number,
+    Timestamp,
+    Duration,
+    Interval,
+    Boolean,
+    Series,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Number => write!(f, "a number"),
+            Self::Timestamp => write!(f, "a timestamp"),
+            Self::Duration => write!(f, "a duration"),
+            Self::Interval => write!(f, "an interval"),
+            Self::Boolean => write!(f, "a boolean"),
+            Self::Series => write!(f, "a series"),
+        }
+    }
+}
+
+/// `left operator right` has no meaning, e.g. `timestamp + timestamp`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TypeError {
+    /// `+`, `-`, `..` or an interval operator such as `contains`.
+    pub operator: &'static str,
+    pub left: Kind,
+    pub right: Kind,
+}
+
+/// Why an expression could not be evaluated.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EvalError {
+    /// An operation on values it makes no sense for.
+    Type(TypeError),
+    /// A series with more than [`MAX_SERIES`] values.
+    SeriesTooLong,
+    /// A series whose step does not move forward in time.
+    NonPositiveStep,
+}
+
+impl From<TypeError> for EvalError {
+    fn from(error: TypeError) -> Self {
+        Self::Type(error)
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Type(error) => error.fmt(f),
+            Self::SeriesTooLong => write!(f, "a series can have at most {MAX_SERIES} values"),
+            Self::NonPositiveStep => write!(f, "a series needs a positive step"),
+        }
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.operator {
+            "+" => write!(f, "cannot add {} to {}", self.right, self.left),
+            "-" => write!(f, "cannot subtract {} from {}", self.right, self.left),
+            ".." => write!(
+                f,
+                "cannot make an interval from {} to {}",
+                self.left, self.right
+            ),
+            operator => write!(
+                f,
+                "cannot use `{operator}` on {} and {}",
+                self.left, self.right
+            ),
+        }
+    }
+}
+
 impl Expression {
     const fn timestamp(timestamp: Option<i64>) -> Self {
         match timestamp {
@@ -106,28 +679,189 @@ impl Expression {
             _ => Self::None,
         }
     }
+    /// A number of seconds as a duration, to the millisecond.
+    fn number_seconds(seconds: f64) -> Self {
+        let milliseconds = (seconds * 1000.0).round();
+        Self::milliseconds((milliseconds.abs() < i64::MAX as f64).then_some(milliseconds as i64))
+    }
     fn milliseconds(milliseconds: Option<i64>) -> Self {
         match milliseconds {
             Some(milliseconds) => Self::Duration(Duration::milliseconds(milliseconds)),
             _ => Self::None,
         }
     }
+
+    /// Whether this is a point in time, a duration or a number, rather than a header,
+    /// blank or invalid line.
+    const fn is_value(&self) -> bool {
+        self.kind().is_some()
+    }
+
+    /// What kind of value this is, `None` for a header, blank or invalid line.
+    pub const fn kind(&self) -> Option<Kind> {
+        match self {
+            Self::Number(_) => Some(Kind::Number),
+            Self::Timestamp(_) => Some(Kind::Timestamp),
+            Self::Duration(_) => Some(Kind::Duration),
+            Self::Interval(..) => Some(Kind::Interval),
+            Self::Boolean(_) => Some(Kind::Boolean),
+            Self::Series(_) => Some(Kind::Series),
+            _ => None,
+        }
+    }
+
+    /// The point in time this stands for: a timestamp, or a number read as an epoch.
+    fn instant(&self) -> Option<i64> {
+        match self {
+            Self::Timestamp(t) => Some(*t),
+            Self::Number(n) => Some(*n as i64),
+            _ => None,
+        }
+    }
+
+    /// The interval from `start` to `end`, or `None` if it would end before it starts.
+    fn span(start: i64, end: Option<i64>) -> Self {
+        match end {
+            Some(end) if end >= start => Self::Interval(start, end),
+            _ => Self::None,
+        }
+    }
+
+    /// `start .. end`, where `end` is either a point in time or the length of the interval.
+    pub fn interval(start: Self, end: Self) -> Self {
+        match (start.instant(), &end) {
+            (Some(s), Self::Duration(d)) => Self::span(s, s.checked_add(d.num_seconds())),
+            (Some(s), _) if end.instant().is_some() => Self::span(s, end.instant()),
+            _ => Self::mismatch("..", start, end),
+        }
+    }
+
+    /// The `start`, `end` or `length` of an interval.
+    fn field(self, field: &str) -> Self {
+        match (self, field) {
+            (Self::Interval(start, _), "start") => Self::Timestamp(start),
+            (Self::Interval(_, end), "end") => Self::Timestamp(end),
+            (Self::Interval(start, end), "length") => Self::seconds(end.checked_sub(start)),
+            (error @ Self::Error(_), _) => error,
+            _ => Self::None,
+        }
+    }
+
+    /// `self operator other` for the interval operators `contains`, `overlaps`,
+    /// `intersect`, `union` and `gap`. The union of two intervals apart also spans the gap
+    /// between them, and the gap between two that overlap is zero.
+    fn relate(self, operator: &'static str, other: Self) -> Self {
+        let Self::Interval(start, end) = self else {
+            return Self::mismatch(operator, self, other);
+        };
+        if let ("contains", Some(t)) = (operator, other.instant()) {
+            return Self::Boolean(start <= t && t < end);
+        }
+        let Self::Interval(s, e) = other else {
+            return Self::mismatch(operator, self, other);
+        };
+        match operator {
+            "contains" => Self::Boolean(start <= s && e <= end),
+            "overlaps" => Self::Boolean(start < e && s < end),
+            "intersect" if start.max(s) < end.min(e) => Self::Interval(start.max(s), end.min(e)),
+            "intersect" => Self::None,
+            "union" => Self::Interval(start.min(s), end.max(e)),
+            _ => Self::seconds(Some(
+                s.saturating_sub(end).max(start.saturating_sub(e)).max(0),
+            )),
+        }
+    }
+
+    /// Points in time `step` apart from `start`, for as long as `keep` is true of their
+    /// index and value. `None` if there would be none, an error if more than [`MAX_SERIES`].
+    fn series(start: Self, step: Step, zone: Zone, keep: impl Fn(usize, i64) -> bool) -> Self {
+        let Some(first) = start.instant() else {
+            return match start {
+                error @ Self::Error(_) => error,
+                _ => Self::None,
+            };
+        };
+        match step.nth(first, 1, zone) {
+            Some(second) if second <= first => return Self::Error(EvalError::NonPositiveStep),
+            None => return Self::None,
+            Some(_) => {}
+        }
+        let mut timestamps = vec![];
+        for n in 0..=MAX_SERIES {
+            match step.nth(first, n, zone) {
+                Some(t) if keep(n, t) && n < MAX_SERIES => timestamps.push(t),
+                Some(t) if keep(n, t) => return Self::Error(EvalError::SeriesTooLong),
+                _ => break,
+            }
+        }
+        match timestamps.is_empty() {
+            true => Self::None,
+            false => Self::Series(timestamps),
+        }
+    }
+
+    /// Every point in time of a series moved by `seconds`.
+    fn shift_series(timestamps: Vec<i64>, seconds: i64) -> Self {
+        let shifted = timestamps.into_iter().map(|t| t.checked_add(seconds));
+        match shifted.collect() {
+            Some(timestamps) => Self::Series(timestamps),
+            None => Self::None,
+        }
+    }
+
+    /// An interval moved by `seconds`.
+    fn shift(start: i64, end: i64, seconds: i64) -> Self {
+        match start.checked_add(seconds) {
+            Some(start) => Self::span(start, end.checked_add(seconds)),
+            None => Self::None,
+        }
+    }
+
+    /// The result of `left operator right` when no coercion makes sense of it: the first
+    /// error of the operands, a [`TypeError`] for two values, or `None` otherwise.
+    fn mismatch(operator: &'static str, left: Self, right: Self) -> Self {
+        match (left, right) {
+            (Self::Error(error), _) | (_, Self::Error(error)) => Self::Error(error),
+            (left, right) => match (left.kind(), right.kind()) {
+                (Some(left), Some(right)) => Self::Error(
+                    TypeError {
+                        operator,
+                        left,
+                        right,
+                    }
+                    .into(),
+                ),
+                _ => Self::None,
+            },
+        }
+    }
 }
 
+// How numbers mix with the other values: added to or subtracted from a timestamp or a
+// duration, a number counts seconds (`now - 60`, `2h + 30`); before a timestamp or a
+// duration it is an epoch (`1748000000 + 1h`, `1748000000 - now`).
+
 impl Add<Self> for Expression {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self {
         match (self, rhs) {
+            (Self::Number(l), Self::Number(r)) => Self::Number(l + r),
             (Self::Duration(l), Self::Duration(r)) => Self::Duration(l + r),
-            (Self::Duration(l), Self::Timestamp(r)) => {
-                Self::timestamp(r.checked_add(l.num_seconds()))
+            (Self::Duration(d), Self::Timestamp(t)) | (Self::Timestamp(t), Self::Duration(d)) => {
+                Self::timestamp(t.checked_add(d.num_seconds()))
             }
-            (Self::Timestamp(l), Self::Duration(r)) => {
-                Self::timestamp(l.checked_add(r.num_seconds()))
+            (Self::Number(n), Self::Timestamp(t)) | (Self::Timestamp(t), Self::Number(n)) => {
+                Self::timestamp(t.checked_add(n as i64))
             }
-            (Self::Timestamp(l), Self::Timestamp(r)) => Self::seconds(l.checked_add(r)),
-            _ => Self::None,
+            (Self::Number(n), duration @ Self::Duration(_)) => Self::Timestamp(n as i64) + duration,
+            (duration @ Self::Duration(_), Self::Number(n)) => duration + Self::number_seconds(n),
+            (Self::Interval(s, e), Self::Duration(d))
+            | (Self::Duration(d), Self::Interval(s, e)) => Self::shift(s, e, d.num_seconds()),
+            (Self::Series(ts), Self::Duration(d)) | (Self::Duration(d), Self::Series(ts)) => {
+                Self::shift_series(ts, d.num_seconds())
+            }
+            (l, r) => Self::mismatch("+", l, r),
         }
     }
 }
@@ -137,68 +871,195 @@ impl Sub<Self> for Expression {
 
     fn sub(self, rhs: Self) -> Self {
         match (self, rhs) {
+            (Self::Number(l), Self::Number(r)) => Self::Number(l - r),
             (Self::Duration(l), Self::Duration(r)) => Self::Duration(l - r),
-            (Self::Duration(l), Self::Timestamp(r)) => {
-                Self::timestamp(l.num_seconds().checked_sub(r))
-            }
             (Self::Timestamp(l), Self::Duration(r)) => {
                 Self::timestamp(l.checked_sub(r.num_seconds()))
             }
             (Self::Timestamp(l), Self::Timestamp(r)) => Self::seconds(l.checked_sub(r)),
-            _ => Self::None,
+            (Self::Timestamp(t), Self::Number(n)) => Self::timestamp(t.checked_sub(n as i64)),
+            (duration @ Self::Duration(_), Self::Number(n)) => duration - Self::number_seconds(n),
+            (Self::Number(n), r @ (Self::Timestamp(_) | Self::Duration(_))) => {
+                Self::Timestamp(n as i64) - r
+            }
+            (Self::Interval(s, e), Self::Duration(d)) => Self::shift(s, e, -d.num_seconds()),
+            (Self::Series(ts), Self::Duration(d)) => Self::shift_series(ts, -d.num_seconds()),
+            (l, r) => Self::mismatch("-", l, r),
+        }
+    }
+}
+
+/// How far apart the values of a series are.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Step {
+    Fixed(Duration),
+    /// Calendar months, such as `1mo` or `1y`, which keep the day of the month.
+    Months(u32),
+}
+
+impl Step {
+    /// The `n`th value of a series starting at `start`. Months are counted in `zone`,
+    /// and from `start` every time, so the 31st stays the 31st in the months that have one.
+    fn nth(self, start: i64, n: usize, zone: Zone) -> Option<i64> {
+        let n = u32::try_from(n).ok()?;
+        match self {
+            Self::Fixed(step) => start.checked_add(step.num_seconds().checked_mul(n.into())?),
+            Self::Months(months) => zone.add_months(start, Months::new(months.checked_mul(n)?)),
         }
     }
 }
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct State<'a> {
-    offset: FixedOffset,
+    zone: Zone,
     now: i64,
     records: &'a [Expression],
+    /// The index of the line being evaluated, which relative references count from.
+    line: usize,
 }
 
 impl<'a> State<'a> {
-    pub const fn new(offset: FixedOffset, now: i64, records: &'a [Expression]) -> Self {
+    /// The state for the line following `records`.
+    pub fn new(zone: impl Into<Zone>, now: i64, records: &'a [Expression]) -> Self {
+        Self::at(zone, now, records, records.len())
+    }
+
+    /// The state for line `line` of a worksheet whose lines are `records`, so references
+    /// can also point below it.
+    pub fn at(zone: impl Into<Zone>, now: i64, records: &'a [Expression], line: usize) -> Self {
         Self {
-            offset,
+            zone: zone.into(),
             now,
             records,
+            line,
         }
     }
+
+    fn above(&self) -> &'a [Expression] {
+        &self.records[..self.line.min(self.records.len())]
+    }
+
+    /// The value a reference finds, without parsing anything.
+    fn resolve(&self, reference: Reference) -> Expression {
+        match reference {
+            Reference::Line(line) => self.records.get(line).cloned().unwrap_or(Expression::None),
+            Reference::Above(n) => self.relative(n),
+            Reference::Previous => self.find(true, Expression::is_value),
+            Reference::First => self.find(false, Expression::is_value),
+            Reference::LastTimestamp => self.find(true, |e| matches!(e, Expression::Timestamp(_))),
+        }
+    }
+
+    /// The line `n` above this one.
+    fn relative(&self, n: usize) -> Expression {
+        let line = self.line.checked_sub(n);
+        line.and_then(|i| self.records.get(i))
+            .cloned()
+            .unwrap_or(Expression::None)
+    }
+
+    /// The first line above matching `predicate`, searching upwards from the closest one
+    /// if `last`, or down from the top otherwise.
+    fn find(&self, last: bool, predicate: impl Fn(&Expression) -> bool) -> Expression {
+        let found = if last {
+            self.above().iter().rev().find(|e| predicate(e))
+        } else {
+            self.above().iter().find(|e| predicate(e))
+        };
+        found.cloned().unwrap_or(Expression::None)
+    }
 }
 
-fn parse_datetime(tz: FixedOffset, ymd: (i32, u32, u32), hms: (u32, u32, u32)) -> Expression {
-    let (year, month, day) = ymd;
-    let (hour, minute, second) = hms;
-    match tz.with_ymd_and_hms(year, month, day, hour, minute, second) {
-        LocalResult::Single(dt) => Expression::Timestamp(dt.timestamp()),
-        _ => Expression::None,
+fn parse_datetime(zone: impl Into<Zone>, ymd: (i32, u32, u32), hms: (u32, u32, u32)) -> Expression {
+    match zone.into().timestamp(ymd, hms) {
+        Some(timestamp) => Expression::Timestamp(timestamp),
+        None => Expression::None,
     }
 }
 
 parser!(
     pub grammar arithmetic(state: &State) for str {
 
-    pub rule expression() -> Expression = precedence!{
+    /// A whole line: an expression, optionally followed by `as` or `in` and a
+    /// conversion such as `iso`, `Asia/Tokyo`, `minutes` or `filetime`.
+    pub rule line() -> (Expression, Option<Conversion>)
+        = e:value() c:(" "+ ("as" / "in") " "+ c:conversion() { c })? { (e, c) }
+
+    /// An expression, except that a lone number is an epoch: `1748000000` is a point in
+    /// time, while `1 + 2` is the number 3.
+    pub rule value() -> Expression
+        = n:signed_number() end() &(![_] / " "+ ("as" / "in") end()) { Expression::Timestamp(n as i64) }
+        / expression()
+
+    rule signed_number() -> f64
+        = "-" n:number() { -n }
+        / number()
+
+    rule conversion() -> Conversion
+        = c:$([_]+) {? Conversion::parse(c).ok_or("conversion") }
+
+    /// An expression, or a series: `<start> every <step> until <end>`, where the end is not
+    /// part of the series.
+    pub rule expression() -> Expression
+        = start:operation() until:(
+            " "+ "every" " "+ step:step() " "+ "until" " "+ end:operation() { (step, end) }
+          )? {
+            match until {
+                Some((step, end)) => match end.instant() {
+                    Some(end) => Expression::series(start, step, state.zone, |_, t| t < end),
+                    None => Expression::None,
+                },
+                None => start,
+            }
+        }
+
+    rule step() -> Step
+        = n:number() "mo" end() { Step::Months(n as u32) }
+        / n:number() "y" end() {? (n as u32).checked_mul(12).map(Step::Months).ok_or("step") }
+        / d:duration_expression() { Step::Fixed(d) }
+
+    rule operation() -> Expression = precedence!{
+        x:(@) " "+ o:interval_operator() " "+ y:@ { x.relate(o, y) }
+        --
+        x:(@) _ ".." _ "+"? _ y:@ { Expression::interval(x, y) }
+        --
         x:(@) _ "+" _ y:@ { x + y }
         x:(@) _ "-" _ y:@ { x - y }
         --
+        x:@ "." f:$("start" / "end" / "length") end() { x.field(f) }
+        --
         "(" _ v:expression() _ ")" { v }
         d:duration_expression() { Expression::Duration(d) }
-        t:timestamp() {t}
+        "series(" _ start:expression() _ "," _ step:step() _ "," _ count:number() _ ")" {
+            Expression::series(start, step, state.zone, |n, _| (n as f64) < count)
+        }
+        l:literal() {l}
         r:record() {r}
     }
 
+    rule interval_operator() -> &'static str
+        = "contains" end() { "contains" }
+        / "overlaps" end() { "overlaps" }
+        / "intersect" end() { "intersect" }
+        / "union" end() { "union" }
+        / "gap" end() { "gap" }
+
     rule _ = quiet!{[' ']*}
     rule end() = !['a'..='z' | 'A'..='Z']
 
-    rule record() -> Expression = "#" + idx:$(['0'..='9']+) {
-        let record_index: usize = idx.parse().unwrap();
-        match state.records.get(record_index - 1) {
-            Some(v) => *v,
-            _ => Expression::None
+    rule record() -> Expression
+        = ("#" + / "$") idx:$(['0'..='9']+) {
+            let record_index: usize = idx.parse().unwrap_or(0);
+            match record_index.checked_sub(1).and_then(|i| state.records.get(i)) {
+                Some(v) => v.clone(),
+                _ => Expression::None
+            }
         }
-    }
+        / ("#" / "$") "-" n:$(['0'..='9']+) { state.relative(n.parse().unwrap_or(usize::MAX)) }
+        / "#prev" !['a'..='z' | '-'] { state.resolve(Reference::Previous) }
+        / "#first" !['a'..='z' | '-'] { state.resolve(Reference::First) }
+        / "#last-timestamp" end() { state.resolve(Reference::LastTimestamp) }
+        / "_" end() { state.relative(1) }
 
     rule days() -> Duration
         = n:number() "d" { Duration::milliseconds((n * 1e3 * 60.0 * 60.0 * 24.0) as i64) }
@@ -230,17 +1091,77 @@ parser!(
 
     rule unquoted_datetime() -> Expression
         = ymd:ydm_fmt_dash() " " + hms:hms_fmt() " " + tz:tz_offset() end() { parse_datetime(tz, ymd, hms) }
-        / ymd:ydm_fmt_dash() " " + hms:hms_fmt() end() { parse_datetime(state.offset, ymd, hms) }
+        / ymd:ydm_fmt_dash() " " + hms:hms_fmt() end() { parse_datetime(state.zone, ymd, hms) }
 
-    rule timestamp() -> Expression
-        = ("-")n:number()end() {Expression::Timestamp(-n as i64)}
+    rule literal() -> Expression
+        = ("-")n:number()end() {Expression::Number(-n)}
         / t:unquoted_datetime() {t}
-        / n:number()end() {Expression::Timestamp(n as i64)}
+        / n:number()end() {Expression::Number(n)}
         / datetime()
         / $("now") {Expression::Timestamp(state.now)}
 
+    rule digit() = ['0'..='9']
+
+    /// An epoch in seconds (optionally fractional), milliseconds, microseconds or
+    /// nanoseconds, told apart by the number of digits. Yields whole seconds.
+    rule epoch() -> i64
+        = n:$(digit()*<19>) !digit() {?
+            // 19 digits can be more than fits in an i64.
+            n.parse::<i64>().map(|n| n.div_euclid(1_000_000_000)).map_err(|_| "epoch")
+        }
+        / n:$(digit()*<16>) !digit() { n.parse::<i64>().unwrap().div_euclid(1_000_000) }
+        / n:$(digit()*<13>) !digit() { n.parse::<i64>().unwrap().div_euclid(1_000) }
+        / n:$(digit()*<10>) ("." digit()+)? !digit() { n.parse().unwrap() }
+
+    rule word() = ['0'..='9' | 'a'..='z' | 'A'..='Z' | '_']+
+
+    rule epoch_match() -> Option<(Range<usize>, i64)>
+        = start:position!() t:epoch() end:position!() !word() { Some((start..end, t)) }
+
+    rule spanned(token: Token, r: rule<()>) -> Vec<(Range<usize>, Token)>
+        = s:position!() r() e:position!() { vec![(s..e, token)] }
+
+    /// A `#UTC`, `#FORMAT` or `#NOW` header, which only counts at the start of a line.
+    /// The rest of a `#NOW` line is an expression, highlighted as usual.
+    rule header() -> Vec<(Range<usize>, Token)>
+        = " "* h:(
+            spanned(Token::Header, <h:$("#UTC" ['+' | '-'] digit()+) &(" "* ![_]) {?
+                get_time_zone(h).map(|_| ()).ok_or("header")
+            }>)
+            / spanned(Token::Header, <h:$("#FORMAT " [_]*) {?
+                get_format(h).map(|_| ()).ok_or("header")
+            }>)
+            / spanned(Token::Header, <"#NOW" &(" "+ [^' '])>)
+        ) { h }
+
+    rule highlighted() -> Vec<(Range<usize>, Token)>
+        = spanned(Token::Reference, <record() {}>)
+        / spanned(Token::DateTime, <(unquoted_datetime() / datetime()) {}>)
+        / spanned(Token::Duration, <duration_expression() {}>)
+        / spanned(Token::Now, <"now" end()>)
+        / spanned(Token::Number, <"-"? number() end()>)
+        / k:spanned(Token::Keyword, <("as" / "in") end()>) " "+ c:spanned(Token::Conversion, <[^' ']+ {}>) {
+            [k, c].concat()
+        }
+        / spanned(Token::Keyword, <interval_operator() {}>)
+        / spanned(Token::Keyword, <("every" / "until" / "series") end()>)
+        / spanned(Token::Duration, <number() ("mo" / "y") end()>)
+        / spanned(Token::Keyword, <"." ("start" / "end" / "length") end()>)
+        / spanned(Token::Operator, <(".." / ['+' | '-' | '(' | ')']) {}>)
+        / word() { vec![] }
+        / [_] { vec![] }
+
+    /// Splits a line into the spans shown in different colours.
+    pub rule tokens() -> Vec<(Range<usize>, Token)>
+        = h:header()? t:highlighted()* { [h.unwrap_or_default(), t.concat()].concat() }
+
+    /// Scans free text for epoch timestamps that stand on their own, skipping
+    /// over any other word so that digits inside identifiers are never matched.
+    pub rule epochs() -> Vec<(Range<usize>, i64)>
+        = v:(epoch_match() / word() { None } / [_] { None })* { v.into_iter().flatten().collect() }
+
     rule number() -> f64
-        = n:$(['0'..='9']+(r"."(['0'..='9']+)?)?) { n.parse().unwrap() }
+        = n:$(['0'..='9']+(r"." !"." ['0'..='9']*)?) { n.parse().unwrap() }
 
     pub rule bad_number() -> f64
         = n:$("a"['0'..='9']+(r"."(['0'..='9']+)?)?) { n.parse().unwrap() }
@@ -280,9 +1201,10 @@ parser!(
 
     rule datetime() -> Expression
         = "'" ymd:ydm_fmt_dash() " " + hms:hms_fmt() " " + tz:tz_offset() "'" { parse_datetime(tz, ymd, hms) }
-        / "'" ymd:ydm_fmt_dash() " " + hms:hms_fmt() "'" { parse_datetime(state.offset, ymd, hms) }
-        / "'" ymd:ydm_fmt_dash() "T" + hms:hms_fmt() "'" { parse_datetime(state.offset, ymd, hms) }
-        / "'" ymd:ydm_fmt_slash() " " + hms:hms_fmt() "'" { parse_datetime(state.offset, ymd, hms) }
+        / "'" ymd:ydm_fmt_dash() " " + hms:hms_fmt() "'" { parse_datetime(state.zone, ymd, hms) }
+        / "'" ymd:ydm_fmt_dash() "T" + hms:hms_fmt() "'" { parse_datetime(state.zone, ymd, hms) }
+        / "'" ymd:ydm_fmt_slash() " " + hms:hms_fmt() "'" { parse_datetime(state.zone, ymd, hms) }
+        / "'" ymd:ydm_fmt_dash() "'" { parse_datetime(state.zone, ymd, (0, 0, 0)) }
 });
 
 #[cfg(test)]
@@ -364,28 +1286,25 @@ mod test {
         let state = State::new(FixedOffset::east_opt(5 * 3600).unwrap(), 0, &records);
         let tz = FixedOffset::east_opt(5 * 3600).unwrap();
         let d = tz.with_ymd_and_hms(2014, 5, 6, 10, 8, 7).unwrap();
+        assert_eq!(arithmetic::value("0", &state), Ok(Expression::Timestamp(0)));
         assert_eq!(
-            arithmetic::expression("0", &state),
-            Ok(Expression::Timestamp(0))
-        );
-        assert_eq!(
-            arithmetic::expression("1006", &state),
+            arithmetic::value("1006", &state),
             Ok(Expression::Timestamp(1006))
         );
         assert_eq!(
-            arithmetic::expression("1006.0", &state),
+            arithmetic::value("1006.0", &state),
             Ok(Expression::Timestamp(1006))
         );
         assert_eq!(
-            arithmetic::expression("1006.1", &state),
+            arithmetic::value("1006.1", &state),
             Ok(Expression::Timestamp(1006))
         );
         assert_eq!(
-            arithmetic::expression("-1006", &state),
+            arithmetic::value("-1006", &state),
             Ok(Expression::Timestamp(-1006))
         );
         assert_eq!(
-            arithmetic::expression("-1006.0", &state),
+            arithmetic::value("-1006.0", &state),
             Ok(Expression::Timestamp(-1006))
         );
         assert_eq!(
@@ -417,67 +1336,261 @@ mod test {
             Ok(Expression::Timestamp(3 - 2 * 60 * 60 + 5 * 60))
         );
         assert_eq!(
-            arithmetic::expression("1 + 2", &state),
-            Ok(Expression::Duration(Duration::seconds(3)))
+            arithmetic::expression("1006", &state),
+            Ok(Expression::Number(1006.0))
+        );
+        assert_eq!(
+            arithmetic::value("1 + 2", &state),
+            Ok(Expression::Number(3.0))
         );
         assert_eq!(
             arithmetic::expression("1s + 2", &state),
-            Ok(Expression::Timestamp(3))
+            Ok(Expression::Duration(Duration::seconds(3)))
+        );
+        assert_eq!(
+            arithmetic::expression("'2014/05/06 10:08:07' + 2", &state),
+            Ok(Expression::Timestamp(d.timestamp() + 2))
+        );
+        assert_eq!(
+            arithmetic::expression("2 + (100 - 500)", &state),
+            Ok(Expression::Number(-398.0))
+        );
+    }
+
+    #[test]
+    fn type_errors() {
+        let records = vec![];
+        let state = State::new(FixedOffset::east_opt(0).unwrap(), 0, &records);
+        let error = |operator, left, right| {
+            Ok(Expression::Error(EvalError::Type(TypeError {
+                operator,
+                left,
+                right,
+            })))
+        };
+        let sum = arithmetic::expression("'2014-05-06 10:08:07' + '2014-05-06 10:08:07'", &state);
+        assert_eq!(sum, error("+", Kind::Timestamp, Kind::Timestamp));
+        assert_eq!(
+            arithmetic::expression("1h - now", &state),
+            error("-", Kind::Duration, Kind::Timestamp)
         );
         assert_eq!(
-            arithmetic::expression("1s - 2", &state),
-            Ok(Expression::Timestamp(-1))
+            arithmetic::expression("2 - (now..now + 1h)", &state),
+            error("-", Kind::Number, Kind::Interval)
         );
+        // The first error is kept through the rest of the expression.
         assert_eq!(
-            arithmetic::expression("'2014-05-06 10:08:07' + '2014-05-06 10:08:07'", &state),
-            Ok(Expression::Duration(Duration::seconds(d.timestamp() * 2)))
+            arithmetic::expression("(now + now) - 1h + 5", &state),
+            error("+", Kind::Timestamp, Kind::Timestamp)
         );
         assert_eq!(
-            arithmetic::expression("'2014/05/06 10:08:07' + 2", &state),
-            Ok(Expression::Duration(Duration::seconds(d.timestamp() + 2)))
+            arithmetic::expression("#9 + now", &state),
+            Ok(Expression::None)
+        );
+        let Ok(Expression::Error(error)) = sum else {
+            unreachable!()
+        };
+        assert_eq!(error.to_string(), "cannot add a timestamp to a timestamp");
+
+        let records = parse(
+            "1748000000
+1748003600
+#2 - #1
+#1 + #2
+#4 + 1h",
+            0,
         );
         assert_eq!(
-            arithmetic::expression("2 + (100 - 500)", &state),
-            Ok(Expression::Timestamp(2 - 400))
+            records[2].expression,
+            Expression::Duration(Duration::hours(1))
+        );
+        assert_eq!(records[3].expression, records[4].expression);
+    }
+    #[test]
+    fn intervals() {
+        let records = vec![Expression::Timestamp(1000), Expression::Timestamp(4600)];
+        let state = State::new(FixedOffset::east_opt(0).unwrap(), 2000, &records);
+        let value = |input| arithmetic::value(input, &state).unwrap();
+        assert_eq!(value("#1..#2"), Expression::Interval(1000, 4600));
+        assert_eq!(value("#1 .. +1h"), Expression::Interval(1000, 4600));
+        assert_eq!(value("now - 1h .. now"), Expression::Interval(-1600, 2000));
+        assert_eq!(
+            value("'2024-01-01' .. +3d"),
+            Expression::Interval(1704067200, 1704067200 + 3 * 86400)
+        );
+        assert_eq!(value("1000..4600"), Expression::Interval(1000, 4600));
+        assert_eq!(value("#2..#1"), Expression::None);
+        assert_eq!(value("(#1..#2).start"), Expression::Timestamp(1000));
+        assert_eq!(value("(#1..#2).end"), Expression::Timestamp(4600));
+        assert_eq!(
+            value("(#1..#2).length"),
+            Expression::Duration(Duration::hours(1))
+        );
+        assert_eq!(value("#1..#2 + 1h"), Expression::Interval(1000, 8200));
+        assert_eq!(value("(#1..#2) + 1h"), Expression::Interval(4600, 8200));
+
+        assert_eq!(value("#1..#2 contains now"), Expression::Boolean(true));
+        assert_eq!(value("#1..#2 contains #2"), Expression::Boolean(false));
+        assert_eq!(
+            value("#1..#2 contains now..+10m"),
+            Expression::Boolean(true)
+        );
+        assert_eq!(value("#1..#2 overlaps #2..+1h"), Expression::Boolean(false));
+        assert_eq!(value("#1..#2 overlaps now..+1d"), Expression::Boolean(true));
+        assert_eq!(
+            value("#1..#2 intersect now..+1d"),
+            Expression::Interval(2000, 4600)
+        );
+        assert_eq!(value("#1..#2 intersect #2..+1h"), Expression::None);
+        assert_eq!(
+            value("#1..+1m union #2..+1m"),
+            Expression::Interval(1000, 4660)
+        );
+        assert_eq!(
+            value("#2..+1m gap #1..+1m"),
+            Expression::Duration(Duration::seconds(3540))
+        );
+        assert_eq!(
+            value("#1..#2 gap now..+1m"),
+            Expression::Duration(Duration::zero())
+        );
+        assert_eq!(
+            value("now contains #1"),
+            Expression::Error(EvalError::Type(TypeError {
+                operator: "contains",
+                left: Kind::Timestamp,
+                right: Kind::Timestamp,
+            }))
+        );
+        let Expression::Error(error) = value("1h..now") else {
+            unreachable!()
+        };
+        assert_eq!(
+            error.to_string(),
+            "cannot make an interval from a duration to a timestamp"
+        );
+    }
+
+    #[test]
+    fn series() {
+        let records = vec![];
+        let state = State::new(FixedOffset::east_opt(3600).unwrap(), 1000, &records);
+        let value = |input| arithmetic::value(input, &state).unwrap();
+        assert_eq!(
+            value("series(now, 15m, 3)"),
+            Expression::Series(vec![1000, 1900, 2800])
+        );
+        assert_eq!(
+            value("series(now, 15m, 3) + 1h"),
+            Expression::Series(vec![4600, 5500, 6400])
+        );
+        assert_eq!(
+            value("now every 1h until now + 4h"),
+            Expression::Series(vec![1000, 4600, 8200, 11800])
+        );
+        // Months are counted in the zone, from the start every time.
+        let first = |month| {
+            FixedOffset::east_opt(3600)
+                .unwrap()
+                .with_ymd_and_hms(2024, month, 31, 0, 0, 0)
+                .single()
+                .map_or(0, |d| d.timestamp())
+        };
+        assert_eq!(
+            value("'2024-01-31 00:00:00' every 2mo until '2024-08-01'"),
+            Expression::Series(vec![first(1), first(3), first(5), first(7)])
+        );
+        let Expression::Series(months) = value("'2024-01-01' every 1mo until '2025-01-01'") else {
+            unreachable!()
+        };
+        assert_eq!(months.len(), 12);
+        assert_eq!(
+            value("'2024-01-01' every 1y until '2024-01-01'"),
+            Expression::None
+        );
+        assert_eq!(
+            value("series(now, 1s, 20000)"),
+            Expression::Error(EvalError::SeriesTooLong)
+        );
+        assert_eq!(
+            EvalError::SeriesTooLong.to_string(),
+            "a series can have at most 10000 values"
+        );
+        assert_eq!(
+            value("series(0, 0s, 3)"),
+            Expression::Error(EvalError::NonPositiveStep)
+        );
+        assert_eq!(
+            value("now every 0mo until now + 1d"),
+            Expression::Error(EvalError::NonPositiveStep)
+        );
+        assert_eq!(
+            EvalError::NonPositiveStep.to_string(),
+            "a series needs a positive step"
+        );
+        // Too many years for a number of months is no step at all.
+        assert!(arithmetic::value("series(now, 400000000y, 2)", &state).is_err());
+        assert!(arithmetic::value("now every 400000000y until now", &state).is_err());
+        assert_eq!(value("series(1h, 1s, 2)"), Expression::None);
+        assert_eq!(
+            highlight("now every 1mo until #2")
+                .into_iter()
+                .map(|(_, token)| token)
+                .collect::<Vec<_>>(),
+            vec![
+                Token::Now,
+                Token::Keyword,
+                Token::Duration,
+                Token::Keyword,
+                Token::Reference
+            ]
         );
     }
+
     #[test]
-    fn timestamps_to_durations() {
+    fn numbers() {
         let records = vec![];
         let state = State::new(FixedOffset::east_opt(0).unwrap(), 0, &records);
         assert_eq!(
             arithmetic::expression("100 - 70", &state),
-            Ok(Expression::Duration(Duration::seconds(30)))
+            Ok(Expression::Number(30.0))
         );
         assert_eq!(
-            arithmetic::expression("100- 70", &state),
-            Ok(Expression::Duration(Duration::seconds(30)))
+            arithmetic::value("100- 70", &state),
+            Ok(Expression::Number(30.0))
         );
         assert_eq!(
-            arithmetic::expression("100-70", &state),
-            Ok(Expression::Duration(Duration::seconds(30)))
+            arithmetic::value("100-70", &state),
+            Ok(Expression::Number(30.0))
         );
         assert_eq!(
-            arithmetic::expression("5 - 3 + 2h", &state),
-            Ok(Expression::Duration(
-                Duration::hours(2) + Duration::seconds(2)
-            ))
+            arithmetic::value("1.5 + 1.25", &state),
+            Ok(Expression::Number(2.75))
         );
+        // Before a duration, a number is an epoch; after one, it counts seconds.
         assert_eq!(
-            arithmetic::expression("(100 - 100) + 2h", &state),
-            Ok(Expression::Duration(Duration::hours(2)))
+            arithmetic::expression("5 - 3 + 2h", &state),
+            Ok(Expression::Timestamp(7202))
         );
         assert_eq!(
             arithmetic::expression("2h + (100 - 100)", &state),
             Ok(Expression::Duration(Duration::hours(2)))
         );
         assert_eq!(
-            arithmetic::expression("2h - (100 - 100)", &state),
+            arithmetic::expression("2h - 100 + 100", &state),
             Ok(Expression::Duration(Duration::hours(2)))
         );
         assert_eq!(
-            arithmetic::expression("2h - 100 + 100", &state),
-            Ok(Expression::Duration(Duration::hours(2)))
+            arithmetic::expression("1h + 2", &state),
+            Ok(Expression::Duration(Duration::seconds(3602)))
+        );
+        assert_eq!(
+            arithmetic::expression("1m - 1.5", &state),
+            Ok(Expression::Duration(Duration::milliseconds(58_500)))
+        );
+        assert_eq!(
+            arithmetic::expression("1748000000 - '2025-05-23 11:33:20'", &state),
+            Ok(Expression::Duration(Duration::zero()))
         );
         assert_eq!(
             arithmetic::expression("(100 - 1s) - (100 + 1s)", &state),
@@ -588,7 +1701,7 @@ mod test {
         );
         // bare number still works
         assert_eq!(
-            arithmetic::expression("2024", &state),
+            arithmetic::value("2024", &state),
             Ok(Expression::Timestamp(2024)),
         );
         // arithmetic with timestamps still works
@@ -622,13 +1735,13 @@ mod test {
         );
         assert_eq!(
             arithmetic::expression("now + 1", &state),
-            Ok(Expression::Duration(Duration::seconds(2)))
+            Ok(Expression::Timestamp(2))
         );
 
         let state = State::new(FixedOffset::east_opt(3600).unwrap(), 10, &records);
         assert_eq!(
             arithmetic::expression("now - 1", &state),
-            Ok(Expression::Duration(Duration::seconds(9)))
+            Ok(Expression::Timestamp(9))
         );
     }
 
@@ -660,9 +1773,384 @@ mod test {
         let input: String = "#UTC+1\n12323123\n'1970-05-23 16:05:23'".to_string();
         let records = parse(&input, 1);
         assert_eq!(records.len(), 3);
-        assert_eq!(records[0].offset, FixedOffset::east_opt(0).unwrap());
-        assert_eq!(records[1].offset, FixedOffset::east_opt(3600).unwrap());
-        assert_eq!(records[2].offset, FixedOffset::east_opt(3600).unwrap());
+        assert_eq!(
+            records[0].zone,
+            Zone::Fixed(FixedOffset::east_opt(0).unwrap())
+        );
+        assert_eq!(
+            records[1].zone,
+            Zone::Fixed(FixedOffset::east_opt(3600).unwrap())
+        );
+        assert_eq!(
+            records[2].zone,
+            Zone::Fixed(FixedOffset::east_opt(3600).unwrap())
+        );
+    }
+
+    #[test]
+    fn offsets() {
+        let hours = |h| FixedOffset::east_opt(h * 3600);
+        assert_eq!(parse_offset("UTC"), hours(0));
+        assert_eq!(parse_offset("utc"), hours(0));
+        assert_eq!(parse_offset("Z"), hours(0));
+        assert_eq!(parse_offset("UTC+5"), hours(5));
+        assert_eq!(parse_offset("-8"), hours(-8));
+        assert_eq!(
+            parse_offset("+05:30"),
+            FixedOffset::east_opt(5 * 3600 + 1800)
+        );
+        assert_eq!(
+            parse_offset("-0330"),
+            FixedOffset::east_opt(-(3 * 3600 + 1800))
+        );
+        assert_eq!(parse_offset("UTC+25"), None);
+        assert_eq!(parse_offset("+05:75"), None);
+        assert_eq!(parse_offset("Tokyo"), None);
+
+        let settings = Settings {
+            zone: Zone::Fixed(hours(2).unwrap()),
+            ..Settings::default()
+        };
+        let records = parse_with("1\n#UTC-1\n2", 0, settings);
+        assert_eq!(records[0].zone, Zone::Fixed(hours(2).unwrap()));
+        assert_eq!(records[2].zone, Zone::Fixed(hours(-1).unwrap()));
+    }
+
+    #[test]
+    fn epochs() {
+        assert_eq!(
+            find_epochs("ts=1748000000 took 1234567890123ms"),
+            vec![(3..13, 1748000000)]
+        );
+        assert_eq!(
+            find_epochs(r#"{"a": 1748000000.250, "b": 1748000000123, "c": 1748000000123456}"#),
+            vec![
+                (6..20, 1748000000),
+                (27..40, 1748000000),
+                (47..63, 1748000000)
+            ]
+        );
+        assert_eq!(
+            find_epochs("1748000000123456789 12345678901 id1748000000 1748000000"),
+            vec![(0..19, 1748000000), (45..55, 1748000000)]
+        );
+        assert_eq!(find_epochs("nothing to see, 200 OK"), vec![]);
+        // Too large for an i64, so not an epoch rather than a crash.
+        assert_eq!(
+            find_epochs("x 9999999999999999999 y 1748000000"),
+            vec![(24..34, 1748000000)]
+        );
+    }
+
+    #[test]
+    fn references() {
+        let records = vec![
+            Expression::Timestamp(10),
+            Expression::Duration(Duration::seconds(5)),
+        ];
+        let state = State::new(FixedOffset::east_opt(0).unwrap(), 0, &records);
+        assert_eq!(
+            arithmetic::expression("#1 + #2", &state),
+            Ok(Expression::Timestamp(15))
+        );
+        assert_eq!(
+            arithmetic::expression("$1 - _", &state),
+            Ok(Expression::Timestamp(5))
+        );
+        assert_eq!(arithmetic::expression("#3", &state), Ok(Expression::None));
+
+        let records = parse(
+            "100\n\n#UTC+1\n1h\n#first + #prev\n#last-timestamp\n#-1 + #-3",
+            0,
+        );
+        let values: Vec<_> = records
+            .iter()
+            .skip(4)
+            .map(|r| r.expression.clone())
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                Expression::Timestamp(3700),
+                Expression::Timestamp(3700),
+                Expression::Timestamp(7300),
+            ]
+        );
+        assert_eq!(parse("#-1\n#-2\n#prev", 0)[1].expression, Expression::None);
+
+        // Lines are evaluated after the lines they refer to, even below them, with the
+        // settings of the headers above them.
+        let records = parse("#3 - #4\n#UTC+1\n'2024-01-01 01:00:00'\n1h", 0);
+        assert_eq!(records[0].expression, Expression::Timestamp(1704063600));
+        assert_eq!(records[0].zone, Zone::Fixed(Utc.fix()));
+        let evaluation = evaluate("#2\n#1 + 1h\n100", 0, Settings::default());
+        assert_eq!(evaluation.circular, vec![0, 1]);
+        assert_eq!(evaluation.cycles, vec![0, 1]);
+        assert_eq!(evaluation.records[2].expression, Expression::Timestamp(100));
+
+        let records = parse("100\n#UTC+1", 0);
+        let record = parse_next("_ + 1m", &records[1].next_settings(), 0, &records);
+        assert_eq!(
+            record.zone,
+            Zone::Fixed(FixedOffset::east_opt(3600).unwrap())
+        );
+        assert_eq!(record.expression, Expression::None);
+        let record = parse_next("#1 + 1m", &Settings::default(), 0, &records);
+        assert_eq!(record.expression, Expression::Timestamp(160));
+    }
+
+    #[test]
+    fn formats() {
+        let records = parse(
+            "1\n#FORMAT iso\n2\n#FORMAT %d/%m\n#UTC+1\n3\n#FORMAT %Q\n#FORMATiso",
+            0,
+        );
+        let formats: Vec<_> = records.iter().map(|r| r.format.clone()).collect();
+        let custom = DateFormat::Strftime("%d/%m".to_string());
+        assert_eq!(
+            formats,
+            vec![
+                DateFormat::Default,
+                DateFormat::Default,
+                DateFormat::Iso,
+                DateFormat::Iso,
+                custom.clone(),
+                custom.clone(),
+                custom.clone(),
+                custom,
+            ]
+        );
+        assert_eq!(records[1].expression, Expression::Format(DateFormat::Iso));
+        assert_eq!(records[6].expression, Expression::None);
+        assert_eq!(records[7].expression, Expression::None);
+    }
+
+    #[test]
+    fn cached() {
+        let mut cache = Cache::default();
+        let mut values = |input: &str, now: i64| -> Vec<Expression> {
+            let evaluation = evaluate_cached(input, now, Settings::default(), &mut cache);
+            let fresh = evaluate(input, now, Settings::default());
+            let values: Vec<_> = evaluation
+                .records
+                .iter()
+                .map(|r| r.expression.clone())
+                .collect();
+            let expected: Vec<_> = fresh.records.iter().map(|r| r.expression.clone()).collect();
+            assert_eq!(values, expected);
+            assert_eq!(evaluation.circular, fresh.circular);
+            assert_eq!(evaluation.cycles, fresh.cycles);
+            values
+        };
+        let seconds = |s| Expression::Duration(Duration::seconds(s));
+        assert_eq!(
+            values("100\n#1 + 1h\nnow\n#prev", 0)[1..],
+            [
+                Expression::Timestamp(3700),
+                Expression::Timestamp(0),
+                Expression::Timestamp(0)
+            ]
+        );
+        // Changing a line changes the lines referring to it, and ticking the lines using now.
+        assert_eq!(
+            values("200\n#1 + 1h\nnow\n#prev", 5)[1],
+            Expression::Timestamp(3800)
+        );
+        assert_eq!(
+            values("200\n#1 + 1h\nnow\n#prev", 5)[3],
+            Expression::Timestamp(5)
+        );
+        // Inserting a line moves what relative references find, but not `#1`.
+        assert_eq!(
+            values("200\n#1 + 1h\n1m\nnow\n#prev", 5)[1..],
+            [
+                Expression::Timestamp(3800),
+                seconds(60),
+                Expression::Timestamp(5),
+                Expression::Timestamp(5)
+            ]
+        );
+        assert_eq!(
+            values("1m\n#1 + 1h\n1m\n#-2", 5)[1..],
+            [seconds(3660), seconds(60), seconds(3660)]
+        );
+        assert_eq!(
+            values("#UTC+1\n1m\n#2 + 1h\n1m\n#-2", 5)[4].clone(),
+            seconds(3660)
+        );
+        assert_eq!(values("#2\n#1", 5), [Expression::None, Expression::None]);
+    }
+
+    #[test]
+    fn pinned_now() {
+        let records = parse(
+            "now\n#NOW 2024-01-15 12:00:00\nnow\n#UTC+1\nnow + 1h\n#NOW now - 1d\nnow\n#2\n#NOW soon",
+            100,
+        );
+        let expressions: Vec<_> = records.iter().map(Expression::from).collect();
+        assert_eq!(
+            expressions,
+            vec![
+                Expression::Timestamp(100),
+                Expression::Timestamp(1705320000),
+                Expression::Timestamp(1705320000),
+                Expression::Offset(FixedOffset::east_opt(3600).unwrap()),
+                Expression::Timestamp(1705323600),
+                Expression::Timestamp(1705233600),
+                Expression::Timestamp(1705233600),
+                Expression::Timestamp(1705320000),
+                Expression::None,
+            ]
+        );
+        assert_eq!(records[1].expression, Expression::Now(1705320000));
+        assert_eq!(records[1].now, None);
+        assert_eq!(records[2].now, Some(1705320000));
+    }
+
+    #[test]
+    fn highlighting() {
+        let line = "#2 + 1h30m - '2024-01-15 12:00:00' in Asia/Tokyo";
+        let tokens: Vec<_> = highlight(line)
+            .into_iter()
+            .map(|(range, token)| (&line[range], token))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                ("#2", Token::Reference),
+                ("+", Token::Operator),
+                ("1h30m", Token::Duration),
+                ("-", Token::Operator),
+                ("'2024-01-15 12:00:00'", Token::DateTime),
+                ("in", Token::Keyword),
+                ("Asia/Tokyo", Token::Conversion),
+            ]
+        );
+        assert_eq!(highlight("#UTC+5"), vec![(0..6, Token::Header)]);
+        assert_eq!(highlight("#FORMAT %H:%M"), vec![(0..13, Token::Header)]);
+        assert_eq!(
+            highlight("#NOW #2"),
+            vec![(0..4, Token::Header), (5..7, Token::Reference)]
+        );
+        // Only the headers that change the settings are highlighted as such.
+        assert_eq!(highlight("#TODO"), vec![]);
+        assert_eq!(
+            highlight("#UTC+99"),
+            vec![(4..5, Token::Operator), (5..7, Token::Number)]
+        );
+        assert_eq!(highlight("#FORMAT nope"), vec![]);
+        assert!(!highlight("1 + #UTC+1").contains(&(4..10, Token::Header)));
+        assert_eq!(
+            highlight("(#1..#2).length contains now"),
+            vec![
+                (0..1, Token::Operator),
+                (1..3, Token::Reference),
+                (3..5, Token::Operator),
+                (5..7, Token::Reference),
+                (7..8, Token::Operator),
+                (8..15, Token::Keyword),
+                (16..24, Token::Keyword),
+                (25..28, Token::Now),
+            ]
+        );
+        assert_eq!(
+            highlight("#prev - #-1"),
+            vec![
+                (0..5, Token::Reference),
+                (6..7, Token::Operator),
+                (8..11, Token::Reference)
+            ]
+        );
+        assert_eq!(
+            highlight(r#"{"ts": now, "n": -5}"#),
+            vec![(7..10, Token::Now), (17..19, Token::Number)]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(check(""), None);
+        assert_eq!(check("now - 1d"), None);
+        assert_eq!(check("#UTC+1"), None);
+        assert_eq!(check("#FORMAT %H:%M"), None);
+        assert_eq!(check("#NOW 0"), None);
+        assert_eq!(check("#9"), None);
+        assert_eq!(check("#last-timestamp - #first"), None);
+        let error = check("  now + 1x").unwrap();
+        assert_eq!(error.position, 9);
+        assert_eq!(
+            error.to_string(),
+            r#"expected one of ".", "d", "h", "m", "ms", "s", a digit"#
+        );
+        assert_eq!(check("now +").unwrap().position, 5);
+        assert_eq!(check(r#"{"ts": nope}"#).unwrap().position, 0);
+    }
+
+    #[test]
+    fn zones() {
+        let tokyo = Zone::parse("Asia/Tokyo").unwrap();
+        assert_eq!(tokyo, Zone::Named(chrono_tz::Asia::Tokyo));
+        assert_eq!(tokyo.to_string(), "Asia/Tokyo");
+        assert_eq!(Zone::parse("+5").unwrap().to_string(), "UTC+05:00");
+        assert_eq!(tokyo.offset_at(0), FixedOffset::east_opt(9 * 3600).unwrap());
+        let new_york = Zone::parse("America/New_York").unwrap();
+        let winter = Utc
+            .with_ymd_and_hms(2024, 1, 15, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        let summer = Utc
+            .with_ymd_and_hms(2024, 7, 15, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        assert_eq!(
+            new_york.offset_at(winter),
+            FixedOffset::west_opt(5 * 3600).unwrap()
+        );
+        assert_eq!(
+            new_york.offset_at(summer),
+            FixedOffset::west_opt(4 * 3600).unwrap()
+        );
+        assert_eq!(
+            Zone::parse("UTC+2"),
+            Some(Zone::Fixed(FixedOffset::east_opt(7200).unwrap()))
+        );
+        assert_eq!(Zone::parse("Mars/Olympus"), None);
+
+        // Datetimes are read in the zone's offset at the time they name, not today's.
+        let settings = Settings {
+            zone: new_york,
+            ..Settings::default()
+        };
+        let records = parse_with(
+            "'2024-01-15 07:00:00'\n'2024-07-15 08:00:00'\n#UTC+1\n'2024-07-15 13:00:00'",
+            0,
+            settings,
+        );
+        let timestamps: Vec<_> = records.iter().map(Expression::from).collect();
+        assert_eq!(timestamps[0], Expression::Timestamp(winter));
+        assert_eq!(timestamps[1], Expression::Timestamp(summer));
+        assert_eq!(timestamps[3], Expression::Timestamp(summer));
+        assert_eq!(records[1].zone, new_york);
+    }
+
+    #[test]
+    fn conversions() {
+        let records = parse(
+            "now in Asia/Tokyo\n#1 - 1h as iso\n#2 in minutes\n2h in bogus",
+            0,
+        );
+        assert_eq!(records[0].expression, Expression::Timestamp(0));
+        assert_eq!(
+            records[0].conversion,
+            Some(Conversion::Zone(Zone::Named(chrono_tz::Asia::Tokyo)))
+        );
+        assert_eq!(records[1].expression, Expression::Timestamp(-3600));
+        assert_eq!(
+            records[1].conversion,
+            Some(Conversion::Format(DateFormat::Iso))
+        );
+        assert_eq!(records[2].expression, Expression::Timestamp(-3600));
+        assert_eq!(records[3].expression, Expression::None);
     }
 
     #[test]
@@ -671,15 +2159,15 @@ mod test {
         let state = State::new(FixedOffset::east_opt(3600).unwrap(), 10, &records);
         assert!(arithmetic::expression("3-", &state).is_err());
         assert_eq!(
-            arithmetic::expression("4324234034234234234039442343", &state),
+            arithmetic::value("4324234034234234234039442343", &state),
             Ok(Expression::Timestamp(i64::MAX))
         );
         assert_eq!(
-            arithmetic::expression("1 + 4324234034234234234039442343", &state),
+            arithmetic::expression("1s + 4324234034234234234039442343", &state),
             Ok(Expression::None)
         );
         assert_eq!(
-            arithmetic::expression("1 - 4324234034234234234039442343", &state),
+            arithmetic::expression("now + 4324234034234234234039442343", &state),
             Ok(Expression::None)
         );
     }
@@ -0,0 +1,116 @@
+//! Worksheets encoded into a URL fragment, so a link reproduces the exact worksheet.
+//!
+//! The input is deflated and written as URL-safe base64 after a version tag, e.g.
+//! `#v1.eJzLSS0...`. The `now` the results were computed against, and the zone used
+//! before the first `#UTC` header, travel with it so the recipient sees the same results
+//! rather than results re-evaluated against their own clock.
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec_with_limit;
+
+use crate::parser::Zone;
+
+const VERSION: &str = "v1.";
+
+/// Links are refused once they inflate past this, so a crafted link cannot exhaust memory.
+const MAX_INPUT: usize = 1024 * 1024;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Permalink {
+    pub input: String,
+    /// The value of `now` pinned for the recipient.
+    pub now: Option<i64>,
+    /// The zone used until the first `#UTC` header.
+    pub zone: Option<Zone>,
+}
+
+impl Permalink {
+    /// The URL fragment for this worksheet, without the leading `#`.
+    pub fn to_fragment(&self) -> String {
+        let mut text = String::new();
+        if let Some(now) = self.now {
+            text.push_str(&format!("now={now}\n"));
+        }
+        if let Some(zone) = self.zone {
+            text.push_str(&format!("zone={zone}\n"));
+        }
+        text.push('\n');
+        text.push_str(&self.input);
+        let compressed = compress_to_vec(text.as_bytes(), 9);
+        format!("{VERSION}{}", URL_SAFE_NO_PAD.encode(compressed))
+    }
+
+    /// Reads a fragment written by [`Permalink::to_fragment`], with or without the leading
+    /// `#`. Anything else, including links from a newer version, gives `None`.
+    pub fn from_fragment(fragment: &str) -> Option<Self> {
+        let encoded = fragment.strip_prefix('#').unwrap_or(fragment);
+        let compressed = URL_SAFE_NO_PAD
+            .decode(encoded.strip_prefix(VERSION)?)
+            .ok()?;
+        let text = decompress_to_vec_with_limit(&compressed, MAX_INPUT).ok()?;
+        let text = String::from_utf8(text).ok()?;
+        let mut permalink = Self {
+            input: String::new(),
+            now: None,
+            zone: None,
+        };
+        // `key=value` lines, then an empty line, then the input.
+        let mut rest = text.as_str();
+        loop {
+            let (line, tail) = rest.split_once('\n')?;
+            rest = tail;
+            match line.split_once('=') {
+                None if line.is_empty() => break,
+                Some(("now", now)) => permalink.now = Some(now.parse().ok()?),
+                Some(("zone", zone)) => permalink.zone = Some(Zone::parse(zone)?),
+                // Fields added later are skipped, so older pages still open newer links.
+                _ => {}
+            }
+        }
+        permalink.input = rest.to_string();
+        Some(permalink)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let permalink = Permalink {
+            input: "#UTC+1\n1748000000\nnow - #2\n\n#2 as iso".to_string(),
+            now: Some(1748003600),
+            zone: Zone::parse("Asia/Tokyo"),
+        };
+        let fragment = permalink.to_fragment();
+        assert!(fragment.starts_with("v1."));
+        assert!(
+            fragment[3..]
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        );
+        assert_eq!(Permalink::from_fragment(&fragment), Some(permalink.clone()));
+        assert_eq!(
+            Permalink::from_fragment(&format!("#{fragment}")),
+            Some(permalink)
+        );
+
+        let bare = Permalink {
+            input: "\n\nnow\n".to_string(),
+            now: None,
+            zone: None,
+        };
+        assert_eq!(Permalink::from_fragment(&bare.to_fragment()), Some(bare));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(Permalink::from_fragment(""), None);
+        assert_eq!(Permalink::from_fragment("#section"), None);
+        assert_eq!(Permalink::from_fragment("v1.not base64"), None);
+        assert_eq!(Permalink::from_fragment("v2.eJwDAAAAAAE"), None);
+    }
+}
@@ -0,0 +1,194 @@
+//! Keeps `#N` references pointing at the same lines while lines are inserted, deleted or
+//! moved around them.
+//!
+//! References resolve by absolute line number, so inserting a line at the top would
+//! otherwise silently shift every reference below it onto the wrong row. [`renumber`]
+//! compares the input before and after an edit, works out where every old line went, and
+//! rewrites the references in the lines that were not themselves edited.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::parser::{Token, highlight};
+
+/// The input after an edit, with its references renumbered.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Renumbered {
+    pub input: String,
+    /// References whose line was deleted by the edit, as a line index and a byte range in
+    /// that line. They are left as they were, and now point at whatever took the line's place.
+    pub orphans: Vec<(usize, Range<usize>)>,
+    /// Where every old line went, or `None` if the edit deleted it.
+    pub moved: Vec<Option<usize>>,
+}
+
+/// Rewrites the references in `new` so that they keep pointing at the lines they pointed
+/// at in `old`. Lines changed by the edit are left alone: their references were typed
+/// against the new numbering.
+pub fn renumber(old: &str, new: &str) -> Renumbered {
+    let old_lines: Vec<_> = old.split('\n').collect();
+    let new_lines: Vec<_> = new.split('\n').collect();
+    let (moved, kept) = diff(&old_lines, &new_lines);
+    let mut orphans = vec![];
+    let mut lines = Vec::with_capacity(new_lines.len());
+    for (index, line) in new_lines.iter().enumerate() {
+        if !kept[index] {
+            lines.push(line.to_string());
+            continue;
+        }
+        let mut rewritten = String::with_capacity(line.len());
+        let mut last = 0;
+        for (range, target) in references(line) {
+            match moved.get(target - 1) {
+                Some(Some(new_target)) => {
+                    let sigil = &line[range.start..range.end - target.to_string().len()];
+                    rewritten.push_str(&line[last..range.start]);
+                    rewritten.push_str(&format!("{sigil}{}", new_target + 1));
+                    last = range.end;
+                }
+                Some(None) => orphans.push((
+                    index,
+                    range.start - last + rewritten.len()..range.end - last + rewritten.len(),
+                )),
+                // References past the end never pointed at a line.
+                None => {}
+            }
+        }
+        rewritten.push_str(&line[last..]);
+        lines.push(rewritten);
+    }
+    Renumbered {
+        input: lines.join("\n"),
+        orphans,
+        moved,
+    }
+}
+
+/// The `#N` and `$N` references in a line, with the line number they point at.
+fn references(line: &str) -> Vec<(Range<usize>, usize)> {
+    highlight(line)
+        .into_iter()
+        .filter(|(_, token)| *token == Token::Reference)
+        .filter_map(|(range, _)| {
+            let digits = line[range.clone()].trim_start_matches(['#', '$']);
+            let target = digits.parse().ok().filter(|target| *target > 0)?;
+            Some((range, target))
+        })
+        .collect()
+}
+
+/// Where every old line went, or `None` if it was deleted, and for every new line whether
+/// it is an old line left untouched (rather than one that was typed or edited).
+fn diff(old: &[&str], new: &[&str]) -> (Vec<Option<usize>>, Vec<bool>) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut moved: Vec<Option<usize>> = vec![None; old.len()];
+    let mut kept = vec![false; new.len()];
+    for i in 0..prefix {
+        moved[i] = Some(i);
+        kept[i] = true;
+    }
+    for i in 0..suffix {
+        moved[old.len() - 1 - i] = Some(new.len() - 1 - i);
+        kept[new.len() - 1 - i] = true;
+    }
+
+    // In the edited block, a line that appears exactly once before and after was moved.
+    let old_block = prefix..old.len() - suffix;
+    let new_block = prefix..new.len() - suffix;
+    let old_counts = count(old, old_block.clone());
+    let new_counts = count(new, new_block.clone());
+    let mut anchors = vec![(prefix, prefix), (old_block.end, new_block.end)];
+    for (line, (n, i)) in &old_counts {
+        if let Some((1, j)) = new_counts.get(line)
+            && *n == 1
+            && !line.trim().is_empty()
+        {
+            moved[*i] = Some(*j);
+            kept[*j] = true;
+            anchors.push((*i, *j));
+        }
+    }
+
+    // Between two lines that stayed in order, the rest were edited in place, in order; any
+    // old lines left over were deleted.
+    anchors.sort();
+    for pair in anchors.windows(2) {
+        let [(old_start, new_start), (old_end, new_end)] = [pair[0], pair[1]];
+        if new_end < new_start {
+            continue;
+        }
+        let edited_old = (old_start..old_end).filter(|i| moved[*i].is_none());
+        let edited_new = (new_start..new_end).filter(|j| !kept[*j]);
+        for (i, j) in edited_old.zip(edited_new).collect::<Vec<_>>() {
+            moved[i] = Some(j);
+        }
+    }
+    (moved, kept)
+}
+
+/// How often each line in `range` occurs, and where it first occurs.
+fn count<'a>(lines: &[&'a str], range: Range<usize>) -> HashMap<&'a str, (usize, usize)> {
+    let mut counts = HashMap::new();
+    for i in range {
+        counts.entry(lines[i]).or_insert((0, i)).0 += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn insert_and_delete() {
+        let old = "1748000000\n2h\n#1 + #2";
+        assert_eq!(
+            renumber(old, "now\n1748000000\n2h\n#1 + #2").input,
+            "now\n1748000000\n2h\n#2 + #3"
+        );
+        assert_eq!(
+            renumber(old, "1748000000\n\n\n2h\n#1 + #2").input,
+            "1748000000\n\n\n2h\n#1 + #4"
+        );
+        let deleted = renumber(old, "2h\n#1 + #2");
+        assert_eq!(deleted.input, "2h\n#1 + #1");
+        assert_eq!(deleted.orphans, vec![(1, 0..2)]);
+        assert_eq!(deleted.moved, vec![None, Some(0), Some(1)]);
+    }
+
+    #[test]
+    fn edits_keep_new_references() {
+        // The edited line's references were typed against the new numbering.
+        assert_eq!(
+            renumber("1\n2\n#1", "0\n1\n2\n#1 - #2").input,
+            "0\n1\n2\n#1 - #2"
+        );
+        // Editing a line in place keeps references to it.
+        assert_eq!(
+            renumber("1\n2\n$2 + _", "1\n3\n$2 + _").input,
+            "1\n3\n$2 + _"
+        );
+        assert_eq!(renumber("1\n#9", "0\n1\n#9").input, "0\n1\n#9");
+    }
+
+    #[test]
+    fn moves() {
+        assert_eq!(
+            renumber("a\nb\n#1 - #2", "b\na\n#1 - #2").input,
+            "b\na\n#2 - #1"
+        );
+        let old = "now\n1h\n#1 + #2\n#3 in ms";
+        let new = "#3 in ms\nnow\n1h\n#1 + #2";
+        assert_eq!(renumber(old, new).input, "#4 in ms\nnow\n1h\n#2 + #3");
+        assert_eq!(
+            renumber(old, new).moved,
+            vec![Some(1), Some(2), Some(3), Some(0)]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Evaluates large worksheets in a web worker, so typing stays responsive.
+//!
+//! The page sends the whole input as an [`Evaluate`] request. The worker keeps the last
+//! [`Worksheet`], so only what changed is evaluated again, and sends back the records that
+//! changed since its last answer in [`Chunk`]s of up to [`CHUNK_LINES`] records, so no
+//! single message is too large.
+
+use gloo_worker::{HandlerId, Worker, WorkerScope};
+use serde::{Deserialize, Serialize};
+
+use crate::format::{Record, Worksheet};
+use crate::parser::{Settings, Zone};
+
+/// How many records each [`Chunk`] carries at most.
+pub const CHUNK_LINES: usize = 1000;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Evaluate {
+    /// Identifies the request, so answers to outdated ones can be ignored.
+    pub id: u64,
+    pub input: String,
+    pub now: i64,
+    /// The [`Zone`] used until the first `#UTC` header, as it is displayed.
+    pub zone: String,
+    /// Whether to send every record, because the page no longer has the last answer.
+    pub full: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Chunk {
+    /// The request this answers.
+    pub id: u64,
+    /// The records that changed, with their line, as written by [`Record::encode`].
+    pub records: Vec<(usize, String)>,
+    /// The number of lines in the worksheet.
+    pub lines: usize,
+    /// Whether any line may use the current time.
+    pub uses_now: bool,
+    /// Lines whose references go round in a circle, sent with the last chunk only.
+    pub circular: Option<Vec<usize>>,
+    /// The lines of `circular` in the circle themselves, empty but in the last chunk.
+    pub cycles: Vec<usize>,
+}
+
+impl Chunk {
+    pub fn records(&self) -> impl Iterator<Item = (usize, Record)> + '_ {
+        self.records
+            .iter()
+            .map(|(line, record)| (*line, Record::decode(record).unwrap_or(Record::None)))
+    }
+}
+
+pub struct Evaluator {
+    sheet: Worksheet,
+    /// The records of the last answer, as sent.
+    sent: Vec<String>,
+}
+
+impl Worker for Evaluator {
+    type Message = ();
+    type Input = Evaluate;
+    type Output = Chunk;
+
+    fn create(_scope: &WorkerScope<Self>) -> Self {
+        Self {
+            sheet: Worksheet::empty(),
+            sent: vec![],
+        }
+    }
+
+    fn update(&mut self, _scope: &WorkerScope<Self>, _msg: Self::Message) {}
+
+    fn received(&mut self, scope: &WorkerScope<Self>, request: Self::Input, id: HandlerId) {
+        let settings = Settings {
+            zone: Zone::parse(&request.zone).unwrap_or(Settings::default().zone),
+            ..Settings::default()
+        };
+        self.sheet.update(request.input, request.now, settings);
+        if request.full {
+            self.sent.clear();
+        }
+        let encoded: Vec<_> = self.sheet.records.iter().map(Record::encode).collect();
+        let changed: Vec<_> = encoded
+            .iter()
+            .enumerate()
+            .filter(|(line, record)| self.sent.get(*line) != Some(*record))
+            .map(|(line, record)| (line, record.clone()))
+            .collect();
+        self.sent = encoded;
+        // An answer always has a chunk, the last one, even when nothing changed.
+        let mut chunks: Vec<_> = changed.chunks(CHUNK_LINES).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+        let count = chunks.len();
+        for (i, records) in chunks.into_iter().enumerate() {
+            let last = i + 1 == count;
+            scope.respond(
+                id,
+                Chunk {
+                    id: request.id,
+                    records: records.to_vec(),
+                    lines: self.sent.len(),
+                    uses_now: self.sheet.uses_now,
+                    circular: last.then(|| self.sheet.circular.clone()),
+                    cycles: match last {
+                        true => self.sheet.cycles.clone(),
+                        false => vec![],
+                    },
+                },
+            );
+        }
+    }
+}